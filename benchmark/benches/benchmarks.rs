@@ -1016,6 +1016,56 @@ fn benchmark_tfhe_encrypted_modulus(c: &mut Criterion) {
     c.bench_function("tfhe_encrypted_modulus", |b| b.iter(tfhe_encrypted_modulus));
 }
 
+// What `#[encrypted(compile)]` expanded `multiplication` below to before circuits were cached:
+// every call built and compiled the circuit with the call's inputs and copied them out
+fn gateway_compile_multiplication_uncached() {
+    use compute::prelude::*;
+
+    fn multiplication(a: u128, b: u128) -> (Circuit, Vec<bool>) {
+        let mut context = WRK17CircuitBuilder::default();
+        let a = &context.input::<128>(&a.into());
+        let b = &context.input::<128>(&b.into());
+        let const_true = &context.input::<128>(&true.into());
+        let const_false = &context.input::<128>(&false.into());
+        let _ = (const_true, const_false);
+
+        let output = {
+            let left = &a;
+            let right = &b;
+            context.mul(left.into(), right.into())
+        };
+        (context.compile(&output), context.inputs().to_vec())
+    }
+
+    let (_circuit, _inputs) = multiplication(12297829382473034410u128, 424242424242u128);
+}
+
+// Binds new inputs to the circuit cached by the macro
+fn gateway_compile_multiplication_cached() {
+    use compute::prelude::*;
+
+    #[encrypted(compile)]
+    fn multiplication(a: u128, b: u128) -> u128 {
+        a * b
+    }
+
+    let (_circuit, _inputs) = multiplication(12297829382473034410u128, 424242424242u128);
+}
+
+// Benchmark 39: Benchmarking benchmark_gateway_compile_multiplication_uncached
+fn benchmark_gateway_compile_multiplication_uncached(c: &mut Criterion) {
+    c.bench_function("gateway_compile_multiplication_uncached", |b| {
+        b.iter(gateway_compile_multiplication_uncached)
+    });
+}
+
+// Benchmark 40: Benchmarking benchmark_gateway_compile_multiplication_cached
+fn benchmark_gateway_compile_multiplication_cached(c: &mut Criterion) {
+    c.bench_function("gateway_compile_multiplication_cached", |b| {
+        b.iter(gateway_compile_multiplication_cached)
+    });
+}
+
 // Configure Criterion with a sample size of 10
 fn custom_criterion() -> Criterion {
     Criterion::default().sample_size(10)
@@ -1064,5 +1114,7 @@ criterion_group!(
         benchmark_tfhe_encrypted_modulus,
         benchmark_gateway_encrypted_mux,
         benchmark_tfhe_encrypted_mux,
+        benchmark_gateway_compile_multiplication_uncached,
+        benchmark_gateway_compile_multiplication_cached,
 );
 criterion_main!(benches);
@@ -1,9 +1,9 @@
 extern crate proc_macro;
-use core::panic;
-
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
 use std::collections::HashSet;
+use syn::visit::{self, Visit};
+use syn::visit_mut::{self, VisitMut};
 use syn::{
     parse_macro_input, BinOp, Expr, ExprAssign, ExprBinary, ExprBlock, ExprIf, ExprLet, ExprMatch,
     ExprReference, ExprUnary, FnArg, ItemFn, Lit, Pat, PatType,
@@ -11,49 +11,61 @@ use syn::{
 
 #[proc_macro_attribute]
 pub fn encrypted(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let mode = parse_macro_input!(attr as syn::Ident).to_string(); // Retrieve the mode (e.g., "compile" or "execute")
-    generate_macro(item, &mode)
+    let mode = parse_macro_input!(attr as syn::Ident); // Retrieve the mode (e.g., "compile" or "execute")
+    let input_fn = parse_macro_input!(item as ItemFn);
+
+    // unsupported constructs are reported as compile errors pointing at the offending code
+    generate_macro(input_fn, &mode)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
-/// Generates the macro code based on the mode (either "compile" or "execute")
-fn generate_macro(item: TokenStream, mode: &str) -> TokenStream {
-    let input_fn = parse_macro_input!(item as ItemFn);
+/// Generates the macro code based on the mode ("compile", "execute" or "artifact")
+fn generate_macro(input_fn: ItemFn, mode: &syn::Ident) -> syn::Result<proc_macro2::TokenStream> {
     let fn_name = &input_fn.sig.ident; // Function name
     let inputs = &input_fn.sig.inputs; // Function input parameters
 
+    let mode_name = mode.to_string();
+    if !["compile", "execute", "artifact"].contains(&mode_name.as_str()) {
+        return Err(syn::Error::new_spanned(
+            mode,
+            format!("unknown mode `{mode_name}`, expected `compile`, `execute` or `artifact`"),
+        ));
+    }
+    let mode = mode_name.as_str();
+
     // get the type of the first input parameter
-    let type_name = if let FnArg::Typed(PatType { ty, .. }) = &inputs[0] {
-        quote! {#ty}
-    } else {
-        panic!("Expected typed argument");
+    let type_name = match inputs.first() {
+        Some(FnArg::Typed(PatType { ty, .. })) => quote! {#ty},
+        Some(receiver) => {
+            return Err(syn::Error::new_spanned(
+                receiver,
+                "encrypted functions can't take `self`",
+            ))
+        }
+        None => {
+            return Err(syn::Error::new(
+                input_fn.sig.paren_token.span.join(),
+                "encrypted functions need at least one parameter",
+            ))
+        }
     };
 
     // get the type of the first output parameter
     let output_type = if let syn::ReturnType::Type(_, ty) = &input_fn.sig.output {
         quote! {#ty}
     } else {
-        panic!("Expected typed return type");
+        return Err(syn::Error::new(
+            input_fn.sig.paren_token.span.close(),
+            "encrypted functions need a return type",
+        ));
     };
 
-    // We need to extract each input's identifier
-    let mapped_inputs = inputs.iter().map(|input| {
-        if let FnArg::Typed(PatType { pat, .. }) = input {
-            if let Pat::Ident(pat_ident) = &**pat {
-                let var_name = &pat_ident.ident;
-                quote! {
-                    let #var_name = &context.input(&#var_name.clone().into());
-                }
-            } else {
-                quote! {}
-            }
-        } else {
-            quote! {}
-        }
-    });
-
     // Extract constants to be added at the top of the function
     let mut constants = vec![];
-    let transformed_block = modify_body(*input_fn.block, &mut constants);
+    let mut body = *input_fn.block;
+    body.stmts = lower_returns(body.stmts, &mut 0)?;
+    let transformed_block = modify_body(body, &mut constants)?;
 
     // remove duplicates
     let mut seen = HashSet::new();
@@ -65,56 +77,113 @@ fn generate_macro(item: TokenStream, mode: &str) -> TokenStream {
     // Collect parameter names dynamically
     let param_names: Vec<_> = inputs
         .iter()
-        .map(|input| {
-            if let FnArg::Typed(PatType { pat, .. }) = input {
-                if let Pat::Ident(pat_ident) = &**pat {
-                    pat_ident.ident.clone()
-                } else {
-                    panic!("Expected identifier pattern");
-                }
-            } else {
-                panic!("Expected typed argument");
-            }
+        .map(|input| match input {
+            FnArg::Typed(PatType { pat, .. }) => match &**pat {
+                Pat::Ident(pat_ident) => Ok(pat_ident.ident.clone()),
+                other => Err(syn::Error::new_spanned(
+                    other,
+                    "expected a parameter name, patterns aren't supported",
+                )),
+            },
+            receiver => Err(syn::Error::new_spanned(
+                receiver,
+                "encrypted functions can't take `self`",
+            )),
         })
-        .collect();
+        .collect::<syn::Result<_>>()?;
 
-    // Dynamically generate the `generate` function calls using the parameter names
-    let match_arms = quote! {
-        match std::any::type_name::<#type_name>() {
-            "bool" => generate::<1, #type_name>(#(#param_names),*),
-            "u8" => generate::<8, #type_name>(#(#param_names),*),
-            "u16" => generate::<16, #type_name>(#(#param_names),*),
-            "u32" => generate::<32, #type_name>(#(#param_names),*),
-            "u64" => generate::<64, #type_name>(#(#param_names),*),
-            "u128" => generate::<128, #type_name>(#(#param_names),*),
-            _ => panic!("Unsupported type"),
-        }
+    // The circuit only depends on N, so it is built once per bit width with placeholder
+    // inputs and every call just binds its arguments to the cached circuit.
+    let build_artifact = quote! {
+        static CACHE: ArtifactCache = ArtifactCache::new();
+
+        let artifact = CACHE.try_get_or_build(N, || -> Result<CircuitArtifact, ComputeError> {
+            let mut context = WRK17CircuitBuilder::default();
+            #(let #param_names = &context.input::<N>(&GarbledUint::<N>::zero());)*
+            #(#constants)*
+
+            // Use the transformed function block (with context.add and if/else replacements)
+            let output = { #transformed_block };
+
+            let layout = InputLayout::garbler_parameters(
+                &[#(stringify!(#param_names)),*],
+                std::any::type_name::<#type_name>(),
+                N,
+                context.inputs(),
+            );
+            let signature = FunctionSignature::new(
+                stringify!(#fn_name),
+                std::any::type_name::<#output_type>(),
+            );
+            CircuitArtifact::new(signature, layout, context.compile(&output.into()))
+        })?;
     };
 
-    // Set the output type and operation logic based on mode
-    let output_type = if mode == "compile" {
-        quote! {(Circuit, Vec<bool>)}
-    } else {
-        quote! {#output_type}
+    let bind_inputs = quote! {
+        #(let #param_names: GarbledUint<N> = #param_names.clone().into();)*
+        let inputs = artifact
+            .layout()
+            .bind(Party::Garbler, &[#(#param_names.bits.as_slice()),*])?;
     };
 
-    let operation = if mode == "compile" {
-        quote! {
-            (context.compile(&output), context.inputs().to_vec())
-        }
+    // Set the signature and operation logic based on mode
+    let (params, output_type, operation, failure) = match mode {
+        "compile" => (
+            quote! {#inputs},
+            quote! {(std::sync::Arc<Circuit>, Vec<bool>)},
+            quote! {
+                #bind_inputs
+                Ok((artifact.shared_circuit(), inputs))
+            },
+            "Failed to build circuit",
+        ),
+        "artifact" => (
+            quote! {},
+            quote! {std::sync::Arc<CircuitArtifact>},
+            quote! {
+                Ok(artifact)
+            },
+            "Failed to build circuit artifact",
+        ),
+        _ => (
+            quote! {#inputs},
+            quote! {#output_type},
+            quote! {
+                #bind_inputs
+                let mut result = artifact.execute(get_executor().as_ref(), &inputs, &[])?;
+                // single-bit results, e.g. of comparisons, are zero-extended to N bits
+                if result.len() == 1 {
+                    result.resize(N, false);
+                }
+                Ok(GarbledUint::<N>::try_new(result)?.into())
+            },
+            "Execution failed",
+        ),
+    };
+
+    // artifact mode takes no arguments, the other modes forward theirs to `generate`
+    let arguments = if mode == "artifact" {
+        quote! {}
     } else {
-        quote! {
-            let compiled_circuit = context.compile(&output.into());
-            let result = context.execute::<N>(&compiled_circuit).expect("Execution failed");
-            result.into()
+        quote! {#(#param_names),*}
+    };
+
+    // Dynamically generate the `generate` function calls using the parameter names
+    let match_arms = quote! {
+        match std::any::type_name::<#type_name>() {
+            "bool" => generate::<1, #type_name>(#arguments),
+            "u8" => generate::<8, #type_name>(#arguments),
+            "u16" => generate::<16, #type_name>(#arguments),
+            "u32" => generate::<32, #type_name>(#arguments),
+            "u64" => generate::<64, #type_name>(#arguments),
+            "u128" => generate::<128, #type_name>(#arguments),
+            _ => Err(ComputeError::UnsupportedWidth(
+                std::mem::size_of::<#type_name>() * 8,
+            )),
         }
     };
 
-    // Build the function body with circuit context, compile, and execute
-    let expanded = quote! {
-        #[allow(non_camel_case_types, non_snake_case, clippy::builtin_type_shadow, unused_assignments)]
-        fn #fn_name<#type_name>(#inputs) -> #output_type
-        where
+    let type_bounds = quote! {
         #type_name: Into<GarbledUint<1>> + From<GarbledUint<1>>
                 + Into<GarbledUint<8>> + From<GarbledUint<8>>
                 + Into<GarbledUint<16>> + From<GarbledUint<16>>
@@ -122,51 +191,65 @@ fn generate_macro(item: TokenStream, mode: &str) -> TokenStream {
                 + Into<GarbledUint<64>> + From<GarbledUint<64>>
                 + Into<GarbledUint<128>> + From<GarbledUint<128>>
                 + Clone,
+    };
+
+    // `try_<name>` returns the errors of building and running the circuit, `<name>` panics on
+    // them
+    let try_fn_name = format_ident!("try_{}", fn_name);
+    let expanded = quote! {
+        #[allow(non_camel_case_types, non_snake_case, clippy::builtin_type_shadow, unused_assignments)]
+        fn #try_fn_name<#type_name>(#params) -> Result<#output_type, ComputeError>
+        where
+            #type_bounds
         {
-            fn generate<const N: usize, #type_name>(#inputs) -> #output_type
+            fn generate<const N: usize, #type_name>(#params) -> Result<#output_type, ComputeError>
             where
                 #type_name: Into<GarbledUint<N>> + From<GarbledUint<N>> + Clone,
             {
-                let mut context = WRK17CircuitBuilder::default();
-                #(#mapped_inputs)*
-                #(#constants)*
-                let const_true = &context.input::<N>(&true.into());
-                let const_false = &context.input::<N>(&false.into());
-
-                // Use the transformed function block (with context.add and if/else replacements)
-                let output = { #transformed_block };
-
+                #build_artifact
                 #operation
             }
 
             #match_arms
         }
+
+        #[allow(non_camel_case_types, non_snake_case, clippy::builtin_type_shadow, dead_code)]
+        fn #fn_name<#type_name>(#params) -> #output_type
+        where
+            #type_bounds
+        {
+            #try_fn_name::<#type_name>(#arguments).expect(#failure)
+        }
     };
 
     // Print the expanded code to stderr
     // println!("Generated code:\n{}", expanded);
 
-    TokenStream::from(expanded)
+    Ok(expanded)
 }
 
 /// Traverse and transform the function body, replacing binary operators and if/else expressions.
 /// Also collects constants to add to the circuit context.
-fn modify_body(block: syn::Block, constants: &mut Vec<proc_macro2::TokenStream>) -> syn::Block {
+fn modify_body(
+    block: syn::Block,
+    constants: &mut Vec<proc_macro2::TokenStream>,
+) -> syn::Result<syn::Block> {
     let stmts = block
         .stmts
         .into_iter()
         .map(|stmt| {
             match stmt {
-                syn::Stmt::Expr(expr, semi_opt) => {
-                    syn::Stmt::Expr(replace_expressions(expr, constants), semi_opt)
-                }
+                syn::Stmt::Expr(expr, semi_opt) => Ok(syn::Stmt::Expr(
+                    replace_expressions(expr, constants)?,
+                    semi_opt,
+                )),
                 syn::Stmt::Local(mut local) => {
                     if let Some(local_init) = &mut local.init {
                         // Replace the initializer expression
                         //local_init.expr =
-                        //    Box::new(replace_expressions(*local_init.expr.clone(), constants));
+                        //    Box::new(replace_expressions(*local_init.expr.clone(), constants)?);
 
-                        let local_expr = replace_expressions(*local_init.expr.clone(), constants);
+                        let local_expr = replace_expressions(*local_init.expr.clone(), constants)?;
 
                         if let syn::Pat::Ident(ref pat_ident) = local.pat {
                             if pat_ident.mutability.is_some() {
@@ -180,32 +263,36 @@ fn modify_body(block: syn::Block, constants: &mut Vec<proc_macro2::TokenStream>)
                             }
                         }
                     }
-                    syn::Stmt::Local(local)
+                    Ok(syn::Stmt::Local(local))
                 }
 
-                other => other,
+                other => Ok(other),
             }
         })
-        .collect();
+        .collect::<syn::Result<_>>()?;
 
-    syn::Block {
+    Ok(syn::Block {
         stmts,
         brace_token: syn::token::Brace::default(),
-    }
+    })
 }
 
-/// Replaces binary operators and if/else expressions with appropriate context calls.
-fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>) -> Expr {
-    match expr {
+/// Replaces binary operators and if/else expressions with appropriate context calls. Fails on
+/// constructs that can't be turned into a circuit.
+fn replace_expressions(
+    expr: Expr,
+    constants: &mut Vec<proc_macro2::TokenStream>,
+) -> syn::Result<Expr> {
+    let expr = match expr {
         // if there is a block, recursively call modify_body
         Expr::Block(ExprBlock { block, .. }) => {
-            let transformed_block = modify_body(block, constants);
+            let transformed_block = modify_body(block, constants)?;
             syn::parse_quote! { #transformed_block }
         }
         // implement assignment
         Expr::Assign(ExprAssign { left, right, .. }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
 
             match right_expr {
                 Expr::Reference(ExprReference { .. }) => {
@@ -220,13 +307,16 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
                 }
             }
         }
-        // return statement
-        Expr::Return(_) => {
-            panic!("Return statement not allowed in circuit macro");
+        // return statement, early returns in `if` branches are lowered by `lower_returns`
+        Expr::Return(expr_return) => {
+            return Err(syn::Error::new_spanned(
+                expr_return,
+                "`return` is only supported in the function body and in `if` branches",
+            ));
         }
         // parentheses to ensure proper order of operations
         Expr::Paren(expr_paren) => {
-            let inner_expr = replace_expressions(*expr_paren.expr, constants);
+            let inner_expr = replace_expressions(*expr_paren.expr, constants)?;
             syn::parse_quote! { (#inner_expr) }
         }
         // boolean literal
@@ -253,9 +343,7 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             lit: Lit::Int(lit_int),
             ..
         }) => {
-            let value = lit_int
-                .base10_parse::<u128>()
-                .expect("Expected an integer literal");
+            let value = lit_int.base10_parse::<u128>()?;
             let const_var = format_ident!("const_{}", value);
             constants.push(quote! {
                 let #const_var = &context.input::<N>(&#value.into()).clone();
@@ -269,8 +357,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Eq(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -284,8 +372,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Ne(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -299,8 +387,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Gt(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -314,8 +402,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Ge(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -329,8 +417,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Lt(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -344,8 +432,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Le(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -359,8 +447,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Add(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = &#left_expr;
                 let right = &#right_expr;
@@ -385,8 +473,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Sub(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -411,8 +499,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Mul(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = &#left_expr;
                 let right = &#right_expr;
@@ -437,8 +525,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Div(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -463,8 +551,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Rem(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -489,8 +577,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::And(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -505,8 +593,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::Or(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -521,8 +609,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::BitAnd(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -548,8 +636,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::BitOr(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -575,8 +663,8 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             op: BinOp::BitXor(_),
             ..
         }) => {
-            let left_expr = replace_expressions(*left, constants);
-            let right_expr = replace_expressions(*right, constants);
+            let left_expr = replace_expressions(*left, constants)?;
+            let right_expr = replace_expressions(*right, constants)?;
             syn::parse_quote! {{
                 let left = #left_expr;
                 let right = #right_expr;
@@ -601,47 +689,39 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             expr,
             ..
         }) => {
-            let single_expr = replace_expressions(*expr, constants);
+            let single_expr = replace_expressions(*expr, constants)?;
             syn::parse_quote! {{
                 let single = #single_expr;
                 context.not(&single.into())
             }}
         }
 
-        /*
         Expr::If(ExprIf {
             cond,
             then_branch,
             else_branch,
             ..
         }) => {
-            let cond_expr = replace_expressions(*cond, constants);
-            let then_block = modify_body(then_branch, constants);
-
-            // If there's an explicit else block, use it; otherwise, continue with remaining expressions
-            let else_expr = if let Some((_, else_expr)) = else_branch {
-                replace_expressions(*else_expr, constants)
-            } else {
-                // Placeholder for remaining function body as the fall-through `else` case
-                //syn::parse_quote! { context.input::<N>(&0u128.into()) }
-                panic!("else branch is required");
-            };
+            // Both branches are computed, so the variables they assign are saved before the `then`
+            // branch, restored for the `else` branch and muxed afterwards.
+            let mut assigned = assigned_variables(|visitor| visitor.visit_block(&then_branch));
+            if let Some((_, else_expr)) = &else_branch {
+                for var in assigned_variables(|visitor| visitor.visit_expr(else_expr)) {
+                    if !assigned.contains(&var) {
+                        assigned.push(var);
+                    }
+                }
+            }
+            let before: Vec<_> = assigned
+                .iter()
+                .map(|var| format_ident!("{}_before_if", var))
+                .collect();
+            let after: Vec<_> = assigned
+                .iter()
+                .map(|var| format_ident!("{}_after_then", var))
+                .collect();
+            let yields_value = else_branch.is_some() && yields_value(&then_branch);
 
-            // Generate code for conditional execution and fall-through
-            syn::parse_quote! {{
-                let cond = #cond_expr;
-                let if_true = #then_block;
-                let if_false = #else_expr;
-                context.mux(&cond.into(), &if_true, &if_false)
-            }}
-        }
-        */
-        Expr::If(ExprIf {
-            cond,
-            then_branch,
-            else_branch,
-            ..
-        }) => {
             // Check if `cond` is an `if let` with a range pattern
             let cond_expr = match *cond {
                 Expr::Let(ExprLet { pat, expr, .. }) => {
@@ -653,9 +733,9 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
                             limits: syn::RangeLimits::Closed(_),
                             ..
                         }) => {
-                            let start_expr = replace_expressions(*start.clone(), constants);
-                            let end_expr = replace_expressions(*end.clone(), constants);
-                            let input_expr = replace_expressions(*expr, constants);
+                            let start_expr = replace_expressions(*start.clone(), constants)?;
+                            let end_expr = replace_expressions(*end.clone(), constants)?;
+                            let input_expr = replace_expressions(*expr, constants)?;
 
                             // Inclusive range with embedded `let` statements for `lhs` and `rhs`
                             syn::parse_quote! {{
@@ -671,9 +751,9 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
                             limits: syn::RangeLimits::HalfOpen(_),
                             ..
                         }) => {
-                            let start_expr = replace_expressions(*start.clone(), constants);
-                            let end_expr = replace_expressions(*end.clone(), constants);
-                            let input_expr = replace_expressions(*expr, constants);
+                            let start_expr = replace_expressions(*start.clone(), constants)?;
+                            let end_expr = replace_expressions(*end.clone(), constants)?;
+                            let input_expr = replace_expressions(*expr, constants)?;
 
                             // Exclusive range with embedded `let` statements for `lhs` and `rhs`
                             syn::parse_quote! {{
@@ -684,44 +764,89 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
                         }
                         // Handle single literal pattern, e.g., `if let 5 = n`
                         syn::Pat::Lit(lit) => {
-                            let lit_expr = replace_expressions(Expr::Lit(lit.clone()), constants);
-                            let input_expr = replace_expressions(*expr, constants);
+                            let lit_expr = replace_expressions(Expr::Lit(lit.clone()), constants)?;
+                            let input_expr = replace_expressions(*expr, constants)?;
 
                             syn::parse_quote! {
                                 context.eq(&#input_expr.into(), &#lit_expr.into())
                             }
                         }
-                        _ => panic!(
-                            "Unsupported pattern in if let: expected a range or literal pattern."
-                        ),
+                        other => {
+                            return Err(syn::Error::new_spanned(
+                                other,
+                                "unsupported pattern in `if let`, expected a literal or a range \
+                                 with both bounds",
+                            ))
+                        }
                     }
                 }
                 ref _other => {
-                    replace_expressions(*cond, constants) // Fallback for non-let conditions
+                    replace_expressions(*cond, constants)? // Fallback for non-let conditions
                 }
             };
 
-            let then_block = modify_body(then_branch, constants);
-
-            // Check if an `else` branch exists, as it's required.
-            let else_expr = if let Some((_, else_expr)) = else_branch {
-                replace_expressions(*else_expr, constants)
-            } else {
-                panic!("else branch is required for range if let");
+            let then_block = modify_body(then_branch, constants)?;
+            let else_expr = match else_branch {
+                Some((_, else_expr)) => Some(replace_expressions(*else_expr, constants)?),
+                None => None,
             };
 
-            // Generate code for conditional execution and chaining
-            syn::parse_quote! {{
-                let cond = #cond_expr;
-                let if_true = #then_block;
-                let if_false = #else_expr;
-                context.mux(&cond.into(), &if_true, &if_false)
-            }}
+            match else_expr {
+                // the values are cloned, as a branch may end in a variable that is used later on
+                Some(else_expr) if yields_value => {
+                    let then_value = clone_tail(syn::parse_quote! { #then_block });
+                    let else_value = clone_tail(else_expr);
+                    if assigned.is_empty() {
+                        // Generate code for conditional execution and chaining
+                        syn::parse_quote! {{
+                            let cond = #cond_expr;
+                            let if_true = #then_value;
+                            let if_false = #else_value;
+                            context.mux(&GateIndex::try_from(cond)?, &if_true, &if_false)
+                        }}
+                    } else {
+                        syn::parse_quote! {{
+                            let cond = GateIndex::try_from(#cond_expr)?;
+                            #(let #before = #assigned.clone();)*
+                            let if_true = #then_value;
+                            #(let #after = std::mem::replace(&mut #assigned, #before);)*
+                            let if_false = #else_value;
+                            #(#assigned = context.mux(&cond, &#after, &#assigned);)*
+                            context.mux(&cond, &if_true, &if_false)
+                        }}
+                    }
+                }
+                // an `if` without a value only updates the variables it assigns
+                else_expr if assigned.is_empty() => {
+                    let else_expr = else_expr.into_iter();
+                    syn::parse_quote! {{
+                        #then_block;
+                        #(#else_expr;)*
+                    }}
+                }
+                else_expr => {
+                    let else_expr = else_expr.into_iter();
+                    syn::parse_quote! {{
+                        let cond = GateIndex::try_from(#cond_expr)?;
+                        #(let #before = #assigned.clone();)*
+                        #then_block;
+                        #(let #after = std::mem::replace(&mut #assigned, #before);)*
+                        #(#else_expr;)*
+                        #(#assigned = context.mux(&cond, &#after, &#assigned);)*
+                    }}
+                }
+            }
         }
 
         // Support match arms with mux and other operations
-        Expr::Match(ExprMatch { expr, arms, .. }) => {
-            let match_expr = replace_expressions(*expr, constants);
+        Expr::Match(ExprMatch {
+            match_token,
+            expr,
+            arms,
+            ..
+        }) => {
+            let scrutinee = quote! {#match_token #expr};
+            let match_expr = replace_expressions(*expr, constants)?;
 
             // Define an input variable to use in range proof processing
             let input = syn::Ident::new("input", proc_macro2::Span::call_site());
@@ -731,97 +856,424 @@ fn replace_expressions(expr: Expr, constants: &mut Vec<proc_macro2::TokenStream>
             let arm_exprs = arms
                 .into_iter()
                 .rev()
-                .fold(None as Option<Expr>, |acc, arm| {
+                .try_fold(None as Option<Expr>, |acc, arm| {
+                    // the last arm is the fallback and is taken whenever no other arm matches
+                    if let (None, Some((if_token, guard))) = (&acc, &arm.guard) {
+                        return Err(syn::Error::new_spanned(
+                            quote! { #if_token #guard },
+                            "the last match arm can't have a guard, add a `_` arm after it",
+                        ));
+                    }
                     let pat = arm.pat;
-                    let body_expr = replace_expressions(*arm.body, constants);
+                    let guard_expr = match arm.guard {
+                        Some((_, guard)) => Some(replace_expressions(*guard, constants)?),
+                        None => None,
+                    };
+                    // a name with a guard binds the matched value, like `x if x > 5`
+                    let binding = match &pat {
+                        syn::Pat::Ident(pat) if guard_expr.is_some() => Some(&pat.ident),
+                        _ => None,
+                    };
+                    let bind = binding.map(|binding| {
+                        quote! {
+                            #[allow(unused_variables)]
+                            let #binding = &#input.clone();
+                        }
+                    });
+                    let body_expr = replace_expressions(*arm.body, constants)?;
 
                     // Create conditional expression for each arm, handling ranges
-                    let cond_expr = match &pat {
-                        // Handle inclusive range pattern (start..=end)
-                        syn::Pat::Range(syn::PatRange {
-                            start: Some(start),
-                            end: Some(end),
-                            limits: syn::RangeLimits::Closed(_),
-                            ..
-                        }) => {
-                            let start = replace_expressions(*start.clone(), constants);
-                            let end = replace_expressions(*end.clone(), constants);
-                            quote! {
-                                let lhs = &context.ge(&#input.into(), &#start.into()).into();
-                                let rhs = &context.le(&#input.into(), &#end.into()).into();
-                                context.and(
-                                    lhs,
-                                    rhs
-                                )
+                    let cond_expr =
+                        match &pat {
+                            // the guard alone decides whether a binding matches
+                            syn::Pat::Ident(_) if binding.is_some() => quote! { true },
+                            // Handle inclusive range pattern (start..=end)
+                            syn::Pat::Range(syn::PatRange {
+                                start: Some(start),
+                                end: Some(end),
+                                limits: syn::RangeLimits::Closed(_),
+                                ..
+                            }) => {
+                                let start = replace_expressions(*start.clone(), constants)?;
+                                let end = replace_expressions(*end.clone(), constants)?;
+                                quote! {
+                                    let lhs = &context.ge(&#input.into(), &#start.into()).into();
+                                    let rhs = &context.le(&#input.into(), &#end.into()).into();
+                                    context.and(
+                                        lhs,
+                                        rhs
+                                    )
+                                }
                             }
-                        }
-                        // Handle exclusive range pattern (start..end)
-                        syn::Pat::Range(syn::PatRange {
-                            start: Some(start),
-                            end: Some(end),
-                            limits: syn::RangeLimits::HalfOpen(_),
-                            ..
-                        }) => {
-                            let start = replace_expressions(*start.clone(), constants);
-                            let end = replace_expressions(*end.clone(), constants);
-                            quote! {
-                                let lhs = &context.ge(&#input.into(), &#start.into()).into();
-                                let rhs = &context.lt(&#input.into(), &#end.into()).into();
-                                context.and(
-                                    lhs,
-                                    rhs
-                                )
+                            // Handle exclusive range pattern (start..end)
+                            syn::Pat::Range(syn::PatRange {
+                                start: Some(start),
+                                end: Some(end),
+                                limits: syn::RangeLimits::HalfOpen(_),
+                                ..
+                            }) => {
+                                let start = replace_expressions(*start.clone(), constants)?;
+                                let end = replace_expressions(*end.clone(), constants)?;
+                                quote! {
+                                    let lhs = &context.ge(&#input.into(), &#start.into()).into();
+                                    let rhs = &context.lt(&#input.into(), &#end.into()).into();
+                                    context.and(
+                                        lhs,
+                                        rhs
+                                    )
+                                }
                             }
-                        }
-                        // Handle single value pattern (e.g., `5`)
-                        syn::Pat::Lit(lit) => {
-                            let lit_expr =
-                                replace_expressions(syn::Expr::Lit(lit.clone()), constants);
-                            quote! {
-                                context.eq(&#input.into(), &#lit_expr.into())
+                            // Handle single value pattern (e.g., `5`)
+                            syn::Pat::Lit(lit) => {
+                                let lit_expr =
+                                    replace_expressions(syn::Expr::Lit(lit.clone()), constants)?;
+                                quote! {
+                                    context.eq(&#input.into(), &#lit_expr.into())
+                                }
                             }
-                        }
 
-                        syn::Pat::Ident(pat) => {
-                            // Create conditional expression for each arm
-                            let cond_expr = replace_expressions(
-                                syn::parse_quote! { #match_expr == #pat },
-                                constants,
-                            );
+                            syn::Pat::Ident(pat) => {
+                                // Create conditional expression for each arm
+                                let cond_expr = replace_expressions(
+                                    syn::parse_quote! { #match_expr == #pat },
+                                    constants,
+                                )?;
 
-                            syn::parse_quote! {{
-                                { #cond_expr }
-                            }}
+                                syn::parse_quote! {{
+                                    { #cond_expr }
+                                }}
+                            }
+                            // Handle the wildcard pattern `_` as default/fallback case
+                            syn::Pat::Wild(_) => quote! { true },
+                            other => return Err(syn::Error::new_spanned(
+                                other,
+                                "unsupported pattern in match arm, expected a literal, a range \
+                                 with both bounds, a name or `_`",
+                            )),
+                        };
+                    let cond_expr = match guard_expr {
+                        None => cond_expr,
+                        Some(guard) if binding.is_some() || matches!(pat, syn::Pat::Wild(_)) => {
+                            quote! { #bind #guard }
                         }
-                        // Handle the wildcard pattern `_` as default/fallback case
-                        syn::Pat::Wild(_) => quote! { true },
-                        other => panic!("{:?}: Unsupported pattern in match arm", other),
+                        Some(guard) => quote! {
+                            let pattern = { #cond_expr };
+                            let guard = { #guard };
+                            context.land(&GateIndex::try_from(pattern)?, &GateIndex::try_from(guard)?)
+                        },
                     };
 
                     // Chain the condition with the body, selecting based on condition
-                    Some(if let Some(else_expr) = acc {
+                    Ok(Some(if let Some(else_expr) = acc {
                         syn::parse_quote! {{
-                            let if_true = { #body_expr };
+                            let if_true = { #bind #body_expr };
                             let if_false = { #else_expr };
                             let cond = { #cond_expr };
-                            context.mux(&cond.into(), &if_true, &if_false)
+                            context.mux(&GateIndex::try_from(cond)?, &if_true, &if_false)
                         }}
                     } else {
                         syn::parse_quote! {{
-                            { #body_expr }
+                            { #bind #body_expr }
                         }}
-                    })
-                });
+                    }))
+                })?;
 
             match arm_exprs {
                 Some(result) => syn::parse_quote! {{
                     #input_binding // Bind `input` at the beginning
                     #result        // Process the chained expressions
                 }},
-                None => panic!("Match expression requires at least one arm"),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        scrutinee,
+                        "`match` requires at least one arm",
+                    ))
+                }
             }
         }
 
         other => other,
+    };
+    Ok(expr)
+}
+
+/// Collects the variables a part of the body assigns, except for the ones it declares itself.
+#[derive(Default)]
+struct Assignments {
+    assigned: Vec<syn::Ident>,
+    declared: HashSet<syn::Ident>,
+}
+
+impl<'ast> Visit<'ast> for Assignments {
+    fn visit_expr_assign(&mut self, expr: &'ast ExprAssign) {
+        self.assign(&expr.left);
+        visit::visit_expr_assign(self, expr);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast ExprBinary) {
+        if is_assign_op(&expr.op) {
+            self.assign(&expr.left);
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_pat_ident(&mut self, pat: &'ast syn::PatIdent) {
+        self.declared.insert(pat.ident.clone());
+        visit::visit_pat_ident(self, pat);
+    }
+}
+
+impl Assignments {
+    fn assign(&mut self, target: &Expr) {
+        if let Expr::Path(path) = target {
+            if let Some(ident) = path.path.get_ident() {
+                if !self.assigned.contains(ident) {
+                    self.assigned.push(ident.clone());
+                }
+            }
+        }
+    }
+}
+
+/// The variables assigned by the part of the body `visit` walks, in order of their first
+/// assignment.
+fn assigned_variables(visit: impl FnOnce(&mut Assignments)) -> Vec<syn::Ident> {
+    let mut assignments = Assignments::default();
+    visit(&mut assignments);
+    assignments
+        .assigned
+        .into_iter()
+        .filter(|var| !assignments.declared.contains(var))
+        .collect()
+}
+
+fn is_assign_op(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::AddAssign(_)
+            | BinOp::SubAssign(_)
+            | BinOp::MulAssign(_)
+            | BinOp::DivAssign(_)
+            | BinOp::RemAssign(_)
+            | BinOp::BitAndAssign(_)
+            | BinOp::BitOrAssign(_)
+            | BinOp::BitXorAssign(_)
+            | BinOp::ShlAssign(_)
+            | BinOp::ShrAssign(_)
+    )
+}
+
+/// Whether a block ends in a value rather than in a statement, e.g. an assignment.
+fn yields_value(block: &syn::Block) -> bool {
+    match block.stmts.last() {
+        Some(syn::Stmt::Expr(expr, None)) => match expr {
+            Expr::Assign(_) => false,
+            Expr::Binary(ExprBinary { op, .. }) => !is_assign_op(op),
+            Expr::If(ExprIf {
+                then_branch,
+                else_branch,
+                ..
+            }) => else_branch.is_some() && yields_value(then_branch),
+            Expr::Block(ExprBlock { block, .. }) => yields_value(block),
+            _ => true,
+        },
+        _ => false,
+    }
+}
+
+/// Clones the value a block ends in, so that ending in a variable doesn't move it.
+fn clone_tail(expr: Expr) -> Expr {
+    match expr {
+        Expr::Block(mut expr_block) => {
+            if let Some(syn::Stmt::Expr(tail, None)) = expr_block.block.stmts.last_mut() {
+                *tail = clone_tail(tail.clone());
+            }
+            Expr::Block(expr_block)
+        }
+        other => syn::parse_quote! { (#other).clone() },
+    }
+}
+
+/// Finds early returns in a part of the body.
+#[derive(Default)]
+struct Returns(bool);
+
+impl<'ast> Visit<'ast> for Returns {
+    fn visit_expr_return(&mut self, _: &'ast syn::ExprReturn) {
+        self.0 = true;
+    }
+}
+
+/// Whether the part of the body `visit` walks returns early anywhere.
+fn contains_return(visit: impl FnOnce(&mut Returns)) -> bool {
+    let mut returns = Returns::default();
+    visit(&mut returns);
+    returns.0
+}
+
+/// Lowers early returns into `if`/`else` expressions, which become mux chains: a `return`
+/// makes its value the value of the block and drops the unreachable statements after it, and
+/// the statements after an `if` that returns in one of its branches are moved into all of its
+/// branches. Returns anywhere else are reported by `replace_expressions`. `branches` counts the
+/// branches merged with the statements after them, to give their variables unique names.
+fn lower_returns(stmts: Vec<syn::Stmt>, branches: &mut usize) -> syn::Result<Vec<syn::Stmt>> {
+    let mut lowered = vec![];
+    let mut stmts = stmts.into_iter();
+    while let Some(stmt) = stmts.next() {
+        match stmt {
+            syn::Stmt::Expr(Expr::Return(expr_return), _) => {
+                let Some(value) = expr_return.expr else {
+                    return Err(syn::Error::new_spanned(
+                        expr_return,
+                        "`return` needs a value in encrypted functions",
+                    ));
+                };
+                lowered.push(syn::Stmt::Expr(*value, None));
+                return Ok(lowered);
+            }
+            syn::Stmt::Expr(Expr::If(expr_if), _)
+                if contains_return(|returns| returns.visit_expr_if(&expr_if)) =>
+            {
+                let rest: Vec<_> = stmts.collect();
+                lowered.push(syn::Stmt::Expr(
+                    Expr::If(if_with_rest(expr_if, &rest, branches)?),
+                    None,
+                ));
+                return Ok(lowered);
+            }
+            other => lowered.push(other),
+        }
+    }
+    Ok(lowered)
+}
+
+/// Moves the statements after an `if` into its branches, adding an `else` branch if needed.
+fn if_with_rest(
+    mut expr_if: ExprIf,
+    rest: &[syn::Stmt],
+    branches: &mut usize,
+) -> syn::Result<ExprIf> {
+    expr_if.then_branch = block_with_rest(expr_if.then_branch, rest, branches)?;
+    let else_block = match expr_if.else_branch.take() {
+        Some((_, else_expr)) => match *else_expr {
+            Expr::Block(ExprBlock { block, .. }) => block,
+            other => syn::parse_quote! {{ #other }},
+        },
+        None => syn::parse_quote! {{}},
+    };
+    let else_block = block_with_rest(else_block, rest, branches)?;
+    expr_if.else_branch = Some((
+        syn::token::Else::default(),
+        Box::new(syn::parse_quote! { #else_block }),
+    ));
+    Ok(expr_if)
+}
+
+fn block_with_rest(
+    block: syn::Block,
+    rest: &[syn::Stmt],
+    branches: &mut usize,
+) -> syn::Result<syn::Block> {
+    // the statements of a branch that returns are merged with the rest, so that its returns are
+    // lowered, and its variables are renamed so that they don't shadow the ones the rest sees;
+    // any other branch keeps its own scope
+    let mut stmts = if contains_return(|returns| returns.visit_block(&block)) {
+        *branches += 1;
+        let mut stmts = block.stmts;
+        rename_locals(&mut stmts, *branches);
+        stmts
+    } else {
+        vec![syn::Stmt::Expr(
+            syn::parse_quote! { #block },
+            Some(syn::token::Semi::default()),
+        )]
+    };
+    stmts.extend(rest.iter().cloned());
+    Ok(syn::Block {
+        brace_token: block.brace_token,
+        stmts: lower_returns(stmts, branches)?,
+    })
+}
+
+/// Gives every variable declared by `stmts` a name of its own, `<name>_<branch>`, which the
+/// user's code can't refer to.
+fn rename_locals(stmts: &mut [syn::Stmt], branch: usize) {
+    for i in 0..stmts.len() {
+        let (before, after) = stmts.split_at_mut(i + 1);
+        let syn::Stmt::Local(local) = &mut before[i] else {
+            continue;
+        };
+        let mut names = Assignments::default();
+        names.visit_pat(&local.pat);
+        for from in names.declared {
+            let to = syn::Ident::new(
+                &format!("{from}_{branch}"),
+                from.span().resolved_at(proc_macro2::Span::mixed_site()),
+            );
+            let mut rename = Rename {
+                from: &from,
+                to: &to,
+            };
+            rename.visit_pat_mut(&mut local.pat);
+            rename.visit_stmts(after);
+        }
+    }
+}
+
+/// Renames a variable wherever it is visible, until it is declared again.
+struct Rename<'a> {
+    from: &'a syn::Ident,
+    to: &'a syn::Ident,
+}
+
+impl Rename<'_> {
+    fn visit_stmts(&mut self, stmts: &mut [syn::Stmt]) {
+        for stmt in stmts {
+            let syn::Stmt::Local(local) = stmt else {
+                self.visit_stmt_mut(stmt);
+                continue;
+            };
+            if let Some(init) = &mut local.init {
+                self.visit_local_init_mut(init);
+            }
+            let mut names = Assignments::default();
+            names.visit_pat(&local.pat);
+            if names.declared.contains(self.from) {
+                return;
+            }
+        }
+    }
+}
+
+impl Rename<'_> {
+    fn rename(&self, ident: &mut syn::Ident) {
+        if ident == self.from {
+            *ident = self.to.clone();
+        }
+    }
+}
+
+impl VisitMut for Rename<'_> {
+    fn visit_path_mut(&mut self, path: &mut syn::Path) {
+        // only a plain name can be a variable
+        if path.segments.len() == 1 {
+            self.rename(&mut path.segments[0].ident);
+        }
+    }
+
+    fn visit_pat_ident_mut(&mut self, pat: &mut syn::PatIdent) {
+        self.rename(&mut pat.ident);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut syn::Block) {
+        self.visit_stmts(&mut block.stmts);
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut syn::Arm) {
+        // a name with a guard binds the matched value, without one it refers to a variable
+        let binds = matches!(&arm.pat, Pat::Ident(pat) if &pat.ident == self.from);
+        if !(binds && arm.guard.is_some()) {
+            visit_mut::visit_arm_mut(self, arm);
+        }
     }
 }
@@ -0,0 +1,207 @@
+//! Batch execution: one circuit evaluated on many records at once.
+//!
+//! A batch runs one protocol instance per record, in lockstep: every step garbles or evaluates
+//! all records in parallel on rayon's thread pool and exchanges their messages together, so a
+//! batch over one connection takes as many round trips as a single run. All instances share one
+//! copy of the circuit.
+//!
+//! Every instance still draws its own randomness and garbles its own tables, as the protocol
+//! requires: tandem has no preprocessing that could be reused across records, so the amortized
+//! setup is the connection, the round trips and the circuit.
+//!
+//! Memory grows with the batch: each step serializes the messages of all records into one
+//! transport message, and both parties hold the messages and protocol state of every record at
+//! the same time. So a step needs about the number of records times the size of one record's
+//! message, e.g. its garbled tables, and the receiver's message size limit (see
+//! [`StreamTransport::with_max_message_size`](crate::transport::StreamTransport::with_max_message_size))
+//! has to allow for it. Split large workloads into batches that fit in memory.
+//!
+//! [`run_garbler`] and [`run_evaluator`] drive one party like the functions in
+//! [`crate::protocol`]. Both parties have to use the batch drivers with the same number of
+//! records. [`Executor::execute_batch`](crate::executor::Executor::execute_batch) runs a batch
+//! on an executor.
+
+use crate::error::ComputeError;
+use crate::evaluator::{Evaluator, GatewayEvaluator};
+use crate::garbler::{Garbler, GatewayGarbler};
+use crate::protocol::{decode_output, encode_output};
+use crate::transport::Transport;
+use anyhow::{anyhow, Context, Result};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+use std::sync::Arc;
+use tandem::Circuit;
+use tokio::task::spawn_blocking;
+use tracing::debug;
+
+/// Checks that both parties provide an input for every record.
+pub(crate) fn check_records(
+    garbler_inputs: &[Vec<bool>],
+    evaluator_inputs: &[Vec<bool>],
+) -> Result<(), ComputeError> {
+    if garbler_inputs.len() != evaluator_inputs.len() {
+        return Err(ComputeError::RecordCount {
+            garbler: garbler_inputs.len(),
+            evaluator: evaluator_inputs.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Starts a garbler for every record and returns them with their first messages.
+pub(crate) fn start_garblers(
+    circuit: &Arc<Circuit>,
+    inputs: &[Vec<bool>],
+) -> Result<(Vec<GatewayGarbler>, Vec<Vec<u8>>), ComputeError> {
+    let started = inputs
+        .par_iter()
+        .map(|input| {
+            GatewayGarbler::start_with_rng(circuit.clone(), input, ChaCha20Rng::from_entropy())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(started.into_iter().unzip())
+}
+
+/// Creates an evaluator for every record.
+pub(crate) fn new_evaluators(
+    circuit: &Arc<Circuit>,
+    inputs: &[Vec<bool>],
+) -> Result<Vec<GatewayEvaluator>, ComputeError> {
+    inputs
+        .par_iter()
+        .map(|input| {
+            GatewayEvaluator::with_rng(circuit.clone(), input, ChaCha20Rng::from_entropy())
+        })
+        .collect()
+}
+
+/// Advances every party with its record's message, in parallel, and returns the parties with
+/// their replies.
+pub(crate) fn step<P, F>(
+    parties: Vec<P>,
+    messages: &[Vec<u8>],
+    next: F,
+) -> Result<(Vec<P>, Vec<Vec<u8>>), ComputeError>
+where
+    P: Send,
+    F: Fn(P, &[u8]) -> Result<(P, Vec<u8>), ComputeError> + Sync,
+{
+    check_messages(parties.len(), messages.len())?;
+    let stepped = parties
+        .into_par_iter()
+        .zip(messages.par_iter())
+        .map(|(party, message)| next(party, message))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(stepped.into_iter().unzip())
+}
+
+/// Computes the output of every record from the garbler's last messages.
+pub(crate) fn outputs(
+    evaluators: Vec<GatewayEvaluator>,
+    messages: &[Vec<u8>],
+) -> Result<Vec<Vec<bool>>, ComputeError> {
+    check_messages(evaluators.len(), messages.len())?;
+    evaluators
+        .into_par_iter()
+        .zip(messages.par_iter())
+        .map(|(evaluator, message)| evaluator.output(message))
+        .collect()
+}
+
+/// Fails unless the other party sent a message for each of the `records`.
+fn check_messages(records: usize, messages: usize) -> Result<(), ComputeError> {
+    if records != messages {
+        return Err(ComputeError::Protocol(anyhow!(
+            "expected messages for {records} records, got {messages}"
+        )));
+    }
+    Ok(())
+}
+
+async fn send<T>(transport: &mut T, messages: &[Vec<u8>]) -> Result<()>
+where
+    T: Transport + ?Sized,
+{
+    transport.send(bincode::serialize(messages)?).await
+}
+
+async fn receive<T>(transport: &mut T, records: usize) -> Result<Vec<Vec<u8>>>
+where
+    T: Transport + ?Sized,
+{
+    let message = transport.receive().await?;
+    let messages: Vec<Vec<u8>> =
+        bincode::deserialize(&message).context("malformed batch message")?;
+    check_messages(records, messages.len())?;
+    Ok(messages)
+}
+
+/// Runs the garbler side of the protocol for every record of `inputs` over one transport and
+/// returns the output of every record.
+pub async fn run_garbler<T>(
+    transport: &mut T,
+    circuit: &Arc<Circuit>,
+    inputs: &[Vec<bool>],
+) -> Result<Vec<Vec<bool>>>
+where
+    T: Transport + ?Sized,
+{
+    let records = inputs.len();
+    let (circuit, inputs) = (circuit.clone(), inputs.to_vec());
+    let (mut garblers, messages) =
+        spawn_blocking(move || start_garblers(&circuit, &inputs)).await??;
+    send(transport, &messages).await?;
+
+    // all records run the same circuit, so they complete at the same step
+    while !garblers.iter().all(GatewayGarbler::is_complete) {
+        let messages = receive(transport, records)
+            .await
+            .context("waiting for the evaluator")?;
+        let (next_garblers, replies) =
+            spawn_blocking(move || step(garblers, &messages, GatewayGarbler::next)).await??;
+        debug!(records, "garbler batch step");
+        garblers = next_garblers;
+        send(transport, &replies).await?;
+    }
+
+    let outputs = receive(transport, records)
+        .await
+        .context("waiting for the outputs")?;
+    outputs.iter().map(|output| decode_output(output)).collect()
+}
+
+/// Runs the evaluator side of the protocol for every record of `inputs` over one transport,
+/// sends the outputs to the garbler and returns them.
+pub async fn run_evaluator<T>(
+    transport: &mut T,
+    circuit: &Arc<Circuit>,
+    inputs: &[Vec<bool>],
+) -> Result<Vec<Vec<bool>>>
+where
+    T: Transport + ?Sized,
+{
+    let records = inputs.len();
+    let (circuit, inputs) = (circuit.clone(), inputs.to_vec());
+    let mut evaluators = spawn_blocking(move || new_evaluators(&circuit, &inputs)).await??;
+
+    loop {
+        let messages = receive(transport, records)
+            .await
+            .context("waiting for the garbler")?;
+
+        if evaluators.iter().all(GatewayEvaluator::is_complete) {
+            let outputs = spawn_blocking(move || outputs(evaluators, &messages)).await??;
+            let encoded: Vec<Vec<u8>> =
+                outputs.iter().map(|output| encode_output(output)).collect();
+            send(transport, &encoded).await?;
+            return Ok(outputs);
+        }
+
+        let (next_evaluators, replies) =
+            spawn_blocking(move || step(evaluators, &messages, GatewayEvaluator::next)).await??;
+        debug!(records, "evaluator batch step");
+        evaluators = next_evaluators;
+        send(transport, &replies).await?;
+    }
+}
@@ -0,0 +1,299 @@
+//! Resumable protocol runs that survive a dropped connection.
+//!
+//! The states of [`GatewayGarbler`] and [`GatewayEvaluator`] can't be serialized, but they are
+//! fully determined by the circuit, the party's input, the seed of its random number generator
+//! and the messages it received. A [`Checkpoint`] records exactly that, so a party can be
+//! rebuilt by replaying the received messages locally, which costs computation but no network
+//! traffic, and the protocol continues where the connection dropped.
+//!
+//! [`run_garbler`] and [`run_evaluator`] drive one party like the functions in
+//! [`crate::protocol`], updating the checkpoint after every step. When a run fails, call it
+//! again with the same checkpoint on a new transport: both parties first tell each other how
+//! many messages they received, resend the one message that was lost if any, and continue. Both
+//! parties have to use the checkpointed drivers, since they add that exchange to the protocol.
+//!
+//! # Security
+//!
+//! A checkpoint contains the party's private input and the seed of all of its randomness in the
+//! clear. Anyone who reads it learns the input and, together with the transcript, can recover the
+//! secrets of the protocol run, including what it hides from the other party. Keep checkpoints
+//! only as long as the run may be resumed, and store them as securely as the inputs themselves.
+//!
+//! Only resume a checkpoint with the same peer, and only once at a time. Replaying the same
+//! randomness against different messages from the other party breaks the security guarantees of
+//! the protocol. Replay checks that both parties agree on how far the run got, but it can't tell
+//! whether the messages come from the same peer; that is up to the caller, e.g. a server that
+//! only resumes a session for the client that started it.
+//!
+//! A checkpoint holds every message received so far, so it grows to the size of the transcript:
+//! for the evaluator this includes all garbled tables.
+
+use crate::evaluator::{Evaluator, GatewayEvaluator};
+use crate::garbler::{Garbler, GatewayGarbler};
+use crate::protocol::{decode_output, encode_output};
+use crate::transport::Transport;
+use anyhow::{bail, ensure, Context, Result};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tandem::Circuit;
+use tokio::task::spawn_blocking;
+use tracing::{debug, info};
+
+/// Version of the serialized checkpoint format, bumped on incompatible changes.
+pub const FORMAT_VERSION: u16 = 1;
+
+/// The party a checkpoint belongs to.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Garbler,
+    Evaluator,
+}
+
+/// Everything needed to rebuild one party of a protocol run.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    role: Role,
+    circuit_hash: [u8; 32],
+    seed: [u8; 32],
+    input: Vec<bool>,
+    received: Vec<Vec<u8>>,
+}
+
+impl Checkpoint {
+    /// Starts a checkpoint for the garbler of `circuit` with `input` and a fresh random seed.
+    pub fn garbler(circuit: &Circuit, input: &[bool]) -> Result<Self> {
+        Checkpoint::new(Role::Garbler, circuit, input)
+    }
+
+    /// Starts a checkpoint for the evaluator of `circuit` with `input` and a fresh random seed.
+    pub fn evaluator(circuit: &Circuit, input: &[bool]) -> Result<Self> {
+        Checkpoint::new(Role::Evaluator, circuit, input)
+    }
+
+    fn new(role: Role, circuit: &Circuit, input: &[bool]) -> Result<Self> {
+        Ok(Checkpoint {
+            role,
+            circuit_hash: circuit_hash(circuit)?,
+            seed: rand::random(),
+            input: input.to_vec(),
+            received: vec![],
+        })
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Number of protocol messages received from the other party so far.
+    pub fn received(&self) -> usize {
+        self.received.len()
+    }
+
+    /// Serializes the checkpoint, see the security notes in the [module docs](self).
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(&(FORMAT_VERSION, self))?)
+    }
+
+    /// Deserializes a checkpoint written by [`Checkpoint::to_bytes`].
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let version: u16 = bincode::deserialize(data).context("reading checkpoint version")?;
+        ensure!(
+            version == FORMAT_VERSION,
+            "unsupported checkpoint format version {version}, expected {FORMAT_VERSION}"
+        );
+        let (_, checkpoint): (u16, Checkpoint) = bincode::deserialize(data)?;
+        Ok(checkpoint)
+    }
+
+    fn check(&self, role: Role, circuit: &Circuit) -> Result<()> {
+        ensure!(
+            self.role == role,
+            "checkpoint of the {:?} can't resume the {:?}",
+            self.role,
+            role
+        );
+        ensure!(
+            self.circuit_hash == circuit_hash(circuit)?,
+            "checkpoint was taken for a different circuit"
+        );
+        Ok(())
+    }
+
+    fn rng(&self) -> ChaCha20Rng {
+        ChaCha20Rng::from_seed(self.seed)
+    }
+}
+
+impl fmt::Debug for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // leaves out the seed and input, which are secret
+        f.debug_struct("Checkpoint")
+            .field("role", &self.role)
+            .field("circuit_hash", &hex::encode(self.circuit_hash))
+            .field("received", &self.received.len())
+            .finish_non_exhaustive()
+    }
+}
+
+fn circuit_hash(circuit: &Circuit) -> Result<[u8; 32]> {
+    Ok(blake3::hash(&bincode::serialize(circuit)?).into())
+}
+
+/// Tells the other party how many messages this party received, and learns the same from it.
+async fn synchronize<T>(transport: &mut T, received: usize) -> Result<usize>
+where
+    T: Transport + ?Sized,
+{
+    transport
+        .send(bincode::serialize(&(received as u64))?)
+        .await?;
+    let message = transport
+        .receive()
+        .await
+        .context("waiting for the other party to synchronize")?;
+    let peer_received: u64 = bincode::deserialize(&message)?;
+    Ok(peer_received as usize)
+}
+
+/// Rebuilds the garbler from `checkpoint`. Returns it with the number of messages it sent and
+/// the last of them.
+fn replay_garbler(
+    circuit: &Arc<Circuit>,
+    checkpoint: &Checkpoint,
+) -> Result<(GatewayGarbler, usize, Vec<u8>)> {
+    let (mut garbler, mut last) =
+        GatewayGarbler::start_with_rng(circuit.clone(), &checkpoint.input, checkpoint.rng())?;
+    for message in &checkpoint.received {
+        ensure!(
+            !garbler.is_complete(),
+            "checkpoint has more messages than steps"
+        );
+        (garbler, last) = garbler.next(message)?;
+    }
+    Ok((garbler, checkpoint.received.len() + 1, last))
+}
+
+/// Rebuilds the evaluator from `checkpoint`. Returns it with the last message it sent, if any.
+fn replay_evaluator(
+    circuit: &Arc<Circuit>,
+    checkpoint: &Checkpoint,
+) -> Result<(GatewayEvaluator, Option<Vec<u8>>)> {
+    let mut evaluator =
+        GatewayEvaluator::with_rng(circuit.clone(), &checkpoint.input, checkpoint.rng())?;
+    let mut last = None;
+    for message in &checkpoint.received {
+        ensure!(
+            !evaluator.is_complete(),
+            "checkpoint has more messages than steps"
+        );
+        let (next, reply) = evaluator.next(message)?;
+        evaluator = next;
+        last = Some(reply);
+    }
+    Ok((evaluator, last))
+}
+
+/// Runs or resumes the garbler side of the protocol from `checkpoint` and returns the output of
+/// the circuit. On failure, `checkpoint` holds the progress made so far.
+pub async fn run_garbler<T>(
+    transport: &mut T,
+    circuit: &Arc<Circuit>,
+    checkpoint: &mut Checkpoint,
+) -> Result<Vec<bool>>
+where
+    T: Transport + ?Sized,
+{
+    checkpoint.check(Role::Garbler, circuit)?;
+    let (circuit_clone, state) = (circuit.clone(), checkpoint.clone());
+    let (mut garbler, sent, last) =
+        spawn_blocking(move || replay_garbler(&circuit_clone, &state)).await??;
+
+    let peer_received = synchronize(transport, checkpoint.received()).await?;
+    if peer_received + 1 == sent {
+        transport.send(last).await?;
+    } else if peer_received != sent {
+        bail!("evaluator received {peer_received} messages, but the garbler sent {sent}");
+    }
+    if checkpoint.received() > 0 {
+        info!(received = checkpoint.received(), "garbler resumed");
+    }
+
+    while !garbler.is_complete() {
+        let message = transport
+            .receive()
+            .await
+            .context("waiting for the evaluator")?;
+        let (next_garbler, reply, message) = spawn_blocking(move || {
+            garbler
+                .next(&message)
+                .map(|(garbler, reply)| (garbler, reply, message))
+        })
+        .await??;
+        checkpoint.received.push(message);
+        debug!(steps_remaining = next_garbler.steps(), "garbler step");
+        garbler = next_garbler;
+        transport.send(reply).await?;
+    }
+
+    let output = transport
+        .receive()
+        .await
+        .context("waiting for the output")?;
+    decode_output(&output)
+}
+
+/// Runs or resumes the evaluator side of the protocol from `checkpoint`, sends the output of the
+/// circuit to the garbler and returns it. On failure, `checkpoint` holds the progress made so
+/// far.
+pub async fn run_evaluator<T>(
+    transport: &mut T,
+    circuit: &Arc<Circuit>,
+    checkpoint: &mut Checkpoint,
+) -> Result<Vec<bool>>
+where
+    T: Transport + ?Sized,
+{
+    checkpoint.check(Role::Evaluator, circuit)?;
+    let (circuit_clone, state) = (circuit.clone(), checkpoint.clone());
+    let (mut evaluator, last) =
+        spawn_blocking(move || replay_evaluator(&circuit_clone, &state)).await??;
+
+    // the evaluator replies to every message it received
+    let sent = checkpoint.received();
+    let peer_received = synchronize(transport, checkpoint.received()).await?;
+    match last {
+        Some(last) if peer_received + 1 == sent => transport.send(last).await?,
+        _ if peer_received == sent => {}
+        _ => bail!("garbler received {peer_received} messages, but the evaluator sent {sent}"),
+    }
+    if checkpoint.received() > 0 {
+        info!(received = checkpoint.received(), "evaluator resumed");
+    }
+
+    loop {
+        let message = transport
+            .receive()
+            .await
+            .context("waiting for the garbler")?;
+
+        if evaluator.is_complete() {
+            let output = spawn_blocking(move || evaluator.output(&message)).await??;
+            transport.send(encode_output(&output)).await?;
+            return Ok(output);
+        }
+
+        let (next_evaluator, reply, message) = spawn_blocking(move || {
+            evaluator
+                .next(&message)
+                .map(|(evaluator, reply)| (evaluator, reply, message))
+        })
+        .await??;
+        checkpoint.received.push(message);
+        debug!(steps_remaining = next_evaluator.steps(), "evaluator step");
+        evaluator = next_evaluator;
+        transport.send(reply).await?;
+    }
+}
@@ -0,0 +1,95 @@
+//! The error type of the compute APIs.
+//!
+//! Executors, the protocol parties and the fallible `try_*` operations on garbled integers return
+//! a [`ComputeError`], so that callers can tell a bad input from a failed protocol run without
+//! parsing messages. The async drivers in [`crate::protocol`] and [`crate::batch`] and the
+//! transports return `anyhow` errors with context; a [`ComputeError`] converts into one with `?`
+//! and back with `From`, which keeps the category of the original error.
+
+use crate::operations::artifact::Party;
+use crate::transport::frame::FrameError;
+use crate::transport::RemoteError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ComputeError {
+    /// The circuit is malformed, e.g. a gate reads a wire that isn't computed yet, or an operation
+    /// got an empty vector of wires where it needs one.
+    #[error("invalid circuit: {0}")]
+    CircuitBuild(String),
+    /// A step of the 2PC protocol failed, e.g. on a malformed message of the other party.
+    #[error("protocol failed: {0:#}")]
+    Protocol(anyhow::Error),
+    /// Exchanging messages with the other party failed.
+    #[error("transport failed: {0:#}")]
+    Transport(anyhow::Error),
+    /// An input or output has another number of bits than the circuit or operation expects.
+    #[error("expected {expected} bits, got {actual}")]
+    InputLength { expected: usize, actual: usize },
+    /// The value of a parameter of an encrypted function has another number of bits than its
+    /// type, as described by the input layout of the circuit.
+    #[error("parameter `{name}: {ty}` expects {expected} bits, got {actual}")]
+    ParameterLength {
+        name: String,
+        ty: String,
+        expected: usize,
+        actual: usize,
+    },
+    /// A party provided values for another number of parameters than the function has.
+    #[error("expected {expected} {party:?} arguments, got {actual}")]
+    ArgumentCount {
+        party: Party,
+        expected: usize,
+        actual: usize,
+    },
+    /// The operation isn't defined for integers of this many bits.
+    #[error("unsupported width of {0} bits")]
+    UnsupportedWidth(usize),
+    /// The parties of a batch provided inputs for different numbers of records.
+    #[error("batch has {garbler} garbler inputs but {evaluator} evaluator inputs")]
+    RecordCount { garbler: usize, evaluator: usize },
+}
+
+impl ComputeError {
+    /// Wraps an error of tandem, which runs the protocol steps.
+    pub(crate) fn protocol(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        ComputeError::Protocol(anyhow::Error::new(error))
+    }
+
+    /// Fails with [`ComputeError::InputLength`] unless `actual` is `expected`.
+    pub(crate) fn check_length(expected: usize, actual: usize) -> Result<(), ComputeError> {
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(ComputeError::InputLength { expected, actual })
+        }
+    }
+}
+
+// Lets `#[encrypted]` convert conditions that already are a single wire with `TryFrom` and `?`
+impl From<std::convert::Infallible> for ComputeError {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
+}
+
+/// Recovers the [`ComputeError`] an `anyhow` error was created from. Any other error is a
+/// transport error if it was caused by the connection or the other party aborting, and a protocol
+/// error otherwise.
+impl From<anyhow::Error> for ComputeError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<ComputeError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        let transport = error.chain().any(|cause| {
+            cause.is::<RemoteError>() || cause.is::<FrameError>() || cause.is::<std::io::Error>()
+        });
+        if transport {
+            ComputeError::Transport(error)
+        } else {
+            ComputeError::Protocol(error)
+        }
+    }
+}
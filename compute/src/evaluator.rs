@@ -1,40 +1,38 @@
-use anyhow::Result;
+use crate::error::ComputeError;
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use std::fmt::Debug;
+use std::sync::Arc;
 use tandem::states::Evaluator as TandemEvaluator;
 use tandem::Circuit;
 
 pub trait Evaluator {
-    fn new(circuit: &Circuit, input: &[bool]) -> Result<Self>
+    fn new(circuit: &Arc<Circuit>, input: &[bool]) -> Result<Self, ComputeError>
     where
         Self: Sized;
-    fn next(self, message: &[u8]) -> Result<(Self, Vec<u8>)>
+    fn next(self, message: &[u8]) -> Result<(Self, Vec<u8>), ComputeError>
     where
         Self: Sized;
     fn steps(&self) -> u32;
     fn is_complete(&self) -> bool;
-    fn output(self, message: &[u8]) -> Result<Vec<bool>>;
+    fn output(self, message: &[u8]) -> Result<Vec<bool>, ComputeError>;
 }
 
 pub struct GatewayEvaluator {
-    evaluator: TandemEvaluator<Circuit, Vec<bool>>,
+    evaluator: TandemEvaluator<Arc<Circuit>, Vec<bool>>,
     steps_remaining: u32,
 }
 
 impl Evaluator for GatewayEvaluator {
-    fn new(circuit: &Circuit, input: &[bool]) -> Result<Self> {
-        let evaluator =
-            TandemEvaluator::new(circuit.clone(), input.to_vec(), ChaCha20Rng::from_entropy())?;
-        let steps_remaining = evaluator.steps();
-        Ok(GatewayEvaluator {
-            evaluator,
-            steps_remaining,
-        })
+    fn new(circuit: &Arc<Circuit>, input: &[bool]) -> Result<Self, ComputeError> {
+        GatewayEvaluator::with_rng(circuit.clone(), input, ChaCha20Rng::from_entropy())
     }
 
-    fn next(self, message: &[u8]) -> Result<(Self, Vec<u8>)> {
-        let (next_state, response) = self.evaluator.run(message)?;
+    fn next(self, message: &[u8]) -> Result<(Self, Vec<u8>), ComputeError> {
+        let (next_state, response) = self
+            .evaluator
+            .run(message)
+            .map_err(ComputeError::protocol)?;
         let steps_remaining = self.steps_remaining - 1;
         Ok((
             GatewayEvaluator {
@@ -53,8 +51,41 @@ impl Evaluator for GatewayEvaluator {
         self.steps_remaining == 0
     }
 
-    fn output(self, message: &[u8]) -> Result<Vec<bool>> {
-        self.evaluator.output(message).map_err(anyhow::Error::new)
+    fn output(self, message: &[u8]) -> Result<Vec<bool>, ComputeError> {
+        self.evaluator
+            .output(message)
+            .map_err(ComputeError::protocol)
+    }
+}
+
+impl GatewayEvaluator {
+    /// Like [`Evaluator::new`], drawing all randomness from a ChaCha20 generator seeded with
+    /// `seed`. Only for tests and debugging, see [`GatewayGarbler::start_seeded`].
+    ///
+    /// [`GatewayGarbler::start_seeded`]: crate::garbler::GatewayGarbler::start_seeded
+    #[cfg(feature = "deterministic")]
+    pub fn new_seeded(
+        circuit: &Arc<Circuit>,
+        input: &[bool],
+        seed: [u8; 32],
+    ) -> Result<Self, ComputeError> {
+        GatewayEvaluator::with_rng(circuit.clone(), input, ChaCha20Rng::from_seed(seed))
+    }
+
+    /// Like [`Evaluator::new`] for a circuit shared with other instances, drawing all randomness
+    /// from `rng`.
+    pub(crate) fn with_rng(
+        circuit: Arc<Circuit>,
+        input: &[bool],
+        rng: ChaCha20Rng,
+    ) -> Result<Self, ComputeError> {
+        let evaluator =
+            TandemEvaluator::new(circuit, input.to_vec(), rng).map_err(ComputeError::protocol)?;
+        let steps_remaining = evaluator.steps();
+        Ok(GatewayEvaluator {
+            evaluator,
+            steps_remaining,
+        })
     }
 }
 
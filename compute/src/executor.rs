@@ -1,24 +1,78 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
-use std::sync::Arc;
-use tandem::Circuit;
+use std::cell::RefCell;
+use std::sync::{Arc, RwLock};
+use tandem::{Circuit, Gate};
 
+use crate::batch;
+use crate::error::ComputeError;
 use crate::evaluator::{Evaluator, GatewayEvaluator};
 use crate::garbler::{Garbler, GatewayGarbler};
+use crate::network::{NetworkEstimate, NetworkProfile, SimulationMode};
+use crate::report::{Direction, ExecutionReport};
+use crate::transcript::{Expected, Transcript};
 
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::Instant;
+use tracing::field::Empty;
+use tracing::{debug, debug_span, info_span, trace};
+
+/// An executor that can be shared between threads and installed with [`set_executor`].
+pub type SharedExecutor = Arc<dyn Executor + Send + Sync>;
 
 // 50 MB per second simulated latency
 const DEFAULT_SIMULATED_LATENCY: f64 = 50.0 * 1024.0 * 1024.0; // bytes per second
-/// A static Lazy instance for holding the singleton LocalSimulator.
-static SINGLETON_EXECUTOR: Lazy<Arc<dyn Executor + Send + Sync>> = Lazy::new(|| {
-    Arc::new(LocalSimulator::new(DEFAULT_SIMULATED_LATENCY)) as Arc<dyn Executor + Send + Sync>
+/// The process-wide executor, a LocalSimulator unless replaced with `set_executor`.
+static GLOBAL_EXECUTOR: Lazy<RwLock<SharedExecutor>> = Lazy::new(|| {
+    RwLock::new(Arc::new(LocalSimulator::new(DEFAULT_SIMULATED_LATENCY)) as SharedExecutor)
 });
 
-/// Provides access to the singleton Executor instance.
-pub fn get_executor() -> Arc<dyn Executor + Send + Sync> {
-    SINGLETON_EXECUTOR.clone()
+thread_local! {
+    /// Executor installed for the current thread by `with_executor`.
+    static SCOPED_EXECUTOR: RefCell<Option<SharedExecutor>> = const { RefCell::new(None) };
+}
+
+/// Provides access to the current Executor: the one installed for this thread by
+/// [`with_executor`] if any, otherwise the process-wide one.
+pub fn get_executor() -> SharedExecutor {
+    SCOPED_EXECUTOR
+        .with(|scoped| scoped.borrow().clone())
+        .unwrap_or_else(global_executor)
+}
+
+/// Replaces the process-wide executor used by encrypted functions and returns the previous one.
+pub fn set_executor(executor: SharedExecutor) -> SharedExecutor {
+    let mut global = GLOBAL_EXECUTOR
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    std::mem::replace(&mut *global, executor)
+}
+
+/// Runs `f` with `executor` installed for the current thread only, restoring the previous
+/// executor afterwards, even if `f` panics.
+pub fn with_executor<R>(executor: SharedExecutor, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<SharedExecutor>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SCOPED_EXECUTOR.with(|scoped| *scoped.borrow_mut() = previous);
+        }
+    }
+
+    let previous = SCOPED_EXECUTOR.with(|scoped| scoped.borrow_mut().replace(executor));
+    let _restore = Restore(previous);
+    f()
+}
+
+fn global_executor() -> SharedExecutor {
+    GLOBAL_EXECUTOR
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
 }
 
 pub trait Executor {
@@ -33,75 +87,408 @@ pub trait Executor {
     /// The result of the simulation as a vector of booleans.
     fn execute(
         &self,
-        circuit: &Circuit,
+        circuit: &Arc<Circuit>,
+        input_contributor: &[bool],
+        input_evaluator: &[bool],
+    ) -> Result<Vec<bool>, ComputeError>;
+
+    /// Executes the protocol like [`Executor::execute`] and also returns statistics about the
+    /// execution. Executors that can't observe the protocol only report the gate counts and the
+    /// total time.
+    fn execute_with_report(
+        &self,
+        circuit: &Arc<Circuit>,
         input_contributor: &[bool],
         input_evaluator: &[bool],
-    ) -> Result<Vec<bool>>;
+    ) -> Result<(Vec<bool>, ExecutionReport), ComputeError> {
+        let started = Instant::now();
+        let mut report = ExecutionReport::new(circuit);
+        let output = self.execute(circuit, input_contributor, input_evaluator)?;
+        report.total = started.elapsed();
+        Ok((output, report))
+    }
+
+    /// Executes the protocol once per record, for the records given by the inputs at the same
+    /// index of `garbler_inputs` and `evaluator_inputs`, and returns the output of every record
+    /// in order. Executors that can run records together amortize the setup across them, see
+    /// [`crate::batch`]; by default they run one after the other.
+    fn execute_batch(
+        &self,
+        circuit: &Arc<Circuit>,
+        garbler_inputs: &[Vec<bool>],
+        evaluator_inputs: &[Vec<bool>],
+    ) -> Result<Vec<Vec<bool>>, ComputeError> {
+        batch::check_records(garbler_inputs, evaluator_inputs)?;
+        garbler_inputs
+            .iter()
+            .zip(evaluator_inputs)
+            .map(|(garbler, evaluator)| self.execute(circuit, garbler, evaluator))
+            .collect()
+    }
 
-    fn instance() -> &'static Arc<dyn Executor + Send + Sync>
+    /// Returns the current executor, see [`get_executor`].
+    fn instance() -> SharedExecutor
     where
         Self: Sized,
     {
-        &SINGLETON_EXECUTOR
+        get_executor()
     }
 }
 
+/// Runs both parties of the protocol in the current process and simulates the network between
+/// them according to a [`NetworkProfile`].
 pub struct LocalSimulator {
-    latency: f64,
+    profile: NetworkProfile,
+    mode: SimulationMode,
+    /// Seeds of the garbler's and the evaluator's randomness, fresh for every execution if unset.
+    seeds: Option<([u8; 32], [u8; 32])>,
 }
 
 impl LocalSimulator {
+    /// A simulator limited to `latency` bytes per second.
     pub fn new(latency: f64) -> Self {
-        LocalSimulator { latency }
+        LocalSimulator::with_profile(NetworkProfile::with_bandwidth(latency))
     }
-}
 
-impl Default for LocalSimulator {
-    fn default() -> Self {
+    pub fn with_profile(profile: NetworkProfile) -> Self {
         LocalSimulator {
-            latency: DEFAULT_SIMULATED_LATENCY,
+            profile,
+            mode: SimulationMode::default(),
+            seeds: None,
         }
     }
-}
 
-impl Executor for LocalSimulator {
-    /// The Multi-Party Computation is performed using the full cryptographic protocol exposed by the
-    /// `Contributor` and `Evaluator`. The messages between contributor and evaluator are exchanged
-    /// using local message queues. This function thus simulates an MPC execution on a local machine
-    /// under ideal network conditions, without any latency or bandwidth restrictions.
-    fn execute(
+    pub fn mode(mut self, mode: SimulationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Seeds the randomness of both parties, so that every execution with the same inputs
+    /// exchanges the same messages, see [`LocalSimulator::replay`]. Only for tests and debugging,
+    /// see [`GatewayGarbler::start_seeded`]. Batches still use fresh randomness.
+    #[cfg(feature = "deterministic")]
+    pub fn seeded(mut self, garbler_seed: [u8; 32], evaluator_seed: [u8; 32]) -> Self {
+        self.seeds = Some((garbler_seed, evaluator_seed));
+        self
+    }
+
+    pub fn profile(&self) -> &NetworkProfile {
+        &self.profile
+    }
+
+    /// Executes the protocol like [`Executor::execute`] and also returns the transcript of all
+    /// messages.
+    pub fn record(
         &self,
-        circuit: &Circuit,
+        circuit: &Arc<Circuit>,
         input_garbler: &[bool],
         input_evaluator: &[bool],
+    ) -> Result<(Vec<bool>, Transcript)> {
+        let mut transcript = Transcript::new();
+        let started = Instant::now();
+        let record = |step, direction, message: &[u8]| {
+            transcript.push(step, direction, started.elapsed(), message);
+            Ok(())
+        };
+        let (output, _) = self.run(circuit, input_garbler, input_evaluator, record)?;
+        Ok((output, transcript))
+    }
+
+    /// Executes the protocol like [`Executor::execute`] and checks every message against
+    /// `transcript`, failing at the first message that differs. The simulator has to be seeded
+    /// like the one that recorded the transcript.
+    pub fn replay(
+        &self,
+        circuit: &Arc<Circuit>,
+        input_garbler: &[bool],
+        input_evaluator: &[bool],
+        transcript: &Transcript,
     ) -> Result<Vec<bool>> {
-        let (mut garbler, mut msg_for_evaluator) = GatewayGarbler::start(circuit, input_garbler)?;
+        let mut expected = Expected::new(transcript);
+        let check = |step, direction, message: &[u8]| expected.check(step, direction, message);
+        let (output, _) = self.run(circuit, input_garbler, input_evaluator, check)?;
+        expected.finish()?;
+        Ok(output)
+    }
 
-        let mut evaluator = GatewayEvaluator::new(circuit, input_evaluator)?;
+    fn rngs(&self) -> (ChaCha20Rng, ChaCha20Rng) {
+        match self.seeds {
+            Some((garbler, evaluator)) => (
+                ChaCha20Rng::from_seed(garbler),
+                ChaCha20Rng::from_seed(evaluator),
+            ),
+            None => (ChaCha20Rng::from_entropy(), ChaCha20Rng::from_entropy()),
+        }
+    }
+
+    /// Runs the protocol, passing every message to `on_message` before it is sent.
+    fn run(
+        &self,
+        circuit: &Arc<Circuit>,
+        input_garbler: &[bool],
+        input_evaluator: &[bool],
+        mut on_message: impl FnMut(u32, Direction, &[u8]) -> Result<()>,
+    ) -> Result<(Vec<bool>, ExecutionReport)> {
+        check_inputs(circuit, input_garbler, input_evaluator)?;
+        let execution_started = Instant::now();
+        let mut report = ExecutionReport::new(circuit);
+        let span = info_span!(
+            "execute",
+            gates = report.gates.total(),
+            and_gates = report.gates.and,
+            bytes = Empty,
+            rounds = Empty,
+        );
+        let _entered = span.enter();
+
+        let (garbler_rng, evaluator_rng) = self.rngs();
+        let mut rng = ChaCha20Rng::from_entropy();
+        let mut estimate = NetworkEstimate::default();
+        let mut transfer = |report: &mut ExecutionReport, step, direction, message: &[u8]| {
+            on_message(step, direction, message)?;
+            report.record_message(step, direction, message.len());
+            let delay = self.profile.message_delay(message.len(), &mut rng);
+            estimate.record(message.len(), delay);
+            trace!(step, ?direction, bytes = message.len(), ?delay, "message");
+            if self.mode == SimulationMode::Sleep {
+                sleep(delay);
+            }
+            anyhow::Ok(())
+        };
 
-        assert_eq!(garbler.steps(), evaluator.steps());
+        let started = Instant::now();
+        let (mut garbler, mut msg_for_evaluator) =
+            GatewayGarbler::start_with_rng(circuit.clone(), input_garbler, garbler_rng)?;
+        report.garbling += started.elapsed();
+        transfer(
+            &mut report,
+            0,
+            Direction::GarblerToEvaluator,
+            &msg_for_evaluator,
+        )?;
+
+        let started = Instant::now();
+        let mut evaluator =
+            GatewayEvaluator::with_rng(circuit.clone(), input_evaluator, evaluator_rng)?;
+        report.evaluating += started.elapsed();
+
+        if garbler.steps() != evaluator.steps() {
+            return Err(ComputeError::Protocol(anyhow!(
+                "the garbler expects {} steps but the evaluator {}",
+                garbler.steps(),
+                evaluator.steps()
+            ))
+            .into());
+        }
         let total_steps = garbler.steps();
 
-        for _ in 0..total_steps {
+        for step in 1..=total_steps {
+            let _step = debug_span!("step", step).entered();
+
+            let started = Instant::now();
             let (next_evaluator, msg_for_garbler) = evaluator.next(&msg_for_evaluator)?;
-            simulate_transfer_time_ms(&msg_for_garbler, &self.latency);
+            report.evaluating += started.elapsed();
+            transfer(
+                &mut report,
+                step,
+                Direction::EvaluatorToGarbler,
+                &msg_for_garbler,
+            )?;
 
             evaluator = next_evaluator;
 
+            let started = Instant::now();
             let (next_garbler, reply) = garbler.next(&msg_for_garbler)?;
-            simulate_transfer_time_ms(&reply, &self.latency);
+            report.garbling += started.elapsed();
+            transfer(&mut report, step, Direction::GarblerToEvaluator, &reply)?;
             garbler = next_garbler;
 
             msg_for_evaluator = reply;
+            report.rounds += 1;
         }
 
+        let started = Instant::now();
         let output = evaluator.output(&msg_for_evaluator)?;
+        report.evaluating += started.elapsed();
+
+        estimate.compute_time = report.garbling + report.evaluating;
+        report.network = Some(estimate);
+        report.total = execution_started.elapsed();
+
+        span.record("bytes", report.total_bytes());
+        span.record("rounds", report.rounds);
+        debug!(total = ?report.total, "execution finished");
+        Ok((output, report))
+    }
+
+    /// Executes the protocol like [`Executor::execute`] and also returns the estimated time the
+    /// execution takes on the simulated network.
+    pub fn simulate(
+        &self,
+        circuit: &Arc<Circuit>,
+        input_garbler: &[bool],
+        input_evaluator: &[bool],
+    ) -> Result<(Vec<bool>, NetworkEstimate), ComputeError> {
+        let (output, report) = self.execute_with_report(circuit, input_garbler, input_evaluator)?;
+        Ok((output, report.network.unwrap_or_default()))
+    }
+}
+
+impl Default for LocalSimulator {
+    fn default() -> Self {
+        LocalSimulator::new(DEFAULT_SIMULATED_LATENCY)
+    }
+}
+
+impl Executor for LocalSimulator {
+    /// The Multi-Party Computation is performed using the full cryptographic protocol exposed by the
+    /// `Contributor` and `Evaluator`. The messages between contributor and evaluator are exchanged
+    /// using local message queues, delayed according to the network profile of the simulator.
+    fn execute(
+        &self,
+        circuit: &Arc<Circuit>,
+        input_garbler: &[bool],
+        input_evaluator: &[bool],
+    ) -> Result<Vec<bool>, ComputeError> {
+        let (output, _) = self.execute_with_report(circuit, input_garbler, input_evaluator)?;
         Ok(output)
     }
+
+    fn execute_with_report(
+        &self,
+        circuit: &Arc<Circuit>,
+        input_garbler: &[bool],
+        input_evaluator: &[bool],
+    ) -> Result<(Vec<bool>, ExecutionReport), ComputeError> {
+        let no_op = |_, _, _: &[u8]| Ok(());
+        Ok(self.run(circuit, input_garbler, input_evaluator, no_op)?)
+    }
+
+    /// Runs all records in lockstep as over one connection, garbling and evaluating them in
+    /// parallel, so the batch takes as many simulated round trips as a single execution. The
+    /// messages of all records in one step are delayed as one message, and held in memory
+    /// together, see [`crate::batch`].
+    fn execute_batch(
+        &self,
+        circuit: &Arc<Circuit>,
+        garbler_inputs: &[Vec<bool>],
+        evaluator_inputs: &[Vec<bool>],
+    ) -> Result<Vec<Vec<bool>>, ComputeError> {
+        batch::check_records(garbler_inputs, evaluator_inputs)?;
+        for (garbler, evaluator) in garbler_inputs.iter().zip(evaluator_inputs) {
+            check_inputs(circuit, garbler, evaluator)?;
+        }
+        let _span = info_span!("execute_batch", records = garbler_inputs.len()).entered();
+
+        let mut rng = ChaCha20Rng::from_entropy();
+        let mut transfer = |messages: &[Vec<u8>]| {
+            let bytes = messages.iter().map(Vec::len).sum();
+            let delay = self.profile.message_delay(bytes, &mut rng);
+            trace!(bytes, ?delay, "batch message");
+            if self.mode == SimulationMode::Sleep {
+                sleep(delay);
+            }
+        };
+
+        let (mut garblers, mut msgs_for_evaluators) =
+            batch::start_garblers(circuit, garbler_inputs)?;
+        transfer(&msgs_for_evaluators);
+        let mut evaluators = batch::new_evaluators(circuit, evaluator_inputs)?;
+
+        while !garblers.iter().all(GatewayGarbler::is_complete) {
+            let (next_evaluators, msgs_for_garblers) =
+                batch::step(evaluators, &msgs_for_evaluators, GatewayEvaluator::next)?;
+            transfer(&msgs_for_garblers);
+            evaluators = next_evaluators;
+
+            let (next_garblers, replies) =
+                batch::step(garblers, &msgs_for_garblers, GatewayGarbler::next)?;
+            transfer(&replies);
+            garblers = next_garblers;
+            msgs_for_evaluators = replies;
+        }
+
+        batch::outputs(evaluators, &msgs_for_evaluators)
+    }
+}
+
+/// Evaluates circuits directly on cleartext bits, without running the 2PC protocol.
+///
+/// Both parties' inputs are visible to this executor, so it offers no privacy at all. It is meant
+/// as a fast oracle in tests and for differential testing against the real protocol.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct PlaintextExecutor;
+
+impl PlaintextExecutor {
+    pub fn new() -> Self {
+        PlaintextExecutor
+    }
+}
+
+impl Executor for PlaintextExecutor {
+    fn execute(
+        &self,
+        circuit: &Arc<Circuit>,
+        input_contributor: &[bool],
+        input_evaluator: &[bool],
+    ) -> Result<Vec<bool>, ComputeError> {
+        check_inputs(circuit, input_contributor, input_evaluator)?;
+
+        let mut contributor = input_contributor.iter();
+        let mut evaluator = input_evaluator.iter();
+        let mut wires = Vec::with_capacity(circuit.gates().len());
+        for gate in circuit.gates() {
+            let value = match *gate {
+                // the lengths were checked above, so the iterators can't run out
+                Gate::InContrib => *contributor.next().unwrap_or(&false),
+                Gate::InEval => *evaluator.next().unwrap_or(&false),
+                Gate::Xor(a, b) => wire(&wires, a)? ^ wire(&wires, b)?,
+                Gate::And(a, b) => wire(&wires, a)? & wire(&wires, b)?,
+                Gate::Not(a) => !wire(&wires, a)?,
+            };
+            wires.push(value);
+        }
+
+        circuit
+            .output_gates()
+            .iter()
+            .map(|&gate| wire(&wires, gate))
+            .collect()
+    }
+
+    fn execute_batch(
+        &self,
+        circuit: &Arc<Circuit>,
+        garbler_inputs: &[Vec<bool>],
+        evaluator_inputs: &[Vec<bool>],
+    ) -> Result<Vec<Vec<bool>>, ComputeError> {
+        batch::check_records(garbler_inputs, evaluator_inputs)?;
+        garbler_inputs
+            .par_iter()
+            .zip(evaluator_inputs)
+            .map(|(garbler, evaluator)| self.execute(circuit, garbler, evaluator))
+            .collect()
+    }
+}
+
+/// Fails with [`ComputeError::InputLength`] unless each party provides one bit per input gate
+/// of `circuit`.
+pub(crate) fn check_inputs(
+    circuit: &Circuit,
+    input_garbler: &[bool],
+    input_evaluator: &[bool],
+) -> Result<(), ComputeError> {
+    ComputeError::check_length(circuit.contrib_inputs(), input_garbler.len())?;
+    ComputeError::check_length(circuit.eval_inputs(), input_evaluator.len())
 }
 
-fn simulate_transfer_time_ms(payload: &[u8], latency: &f64) {
-    let bytes_size = payload.len() as f64;
-    let transfer_time = bytes_size / latency * 1000.0; // Convert seconds to milliseconds
-    sleep(Duration::from_millis(transfer_time as u64))
+/// Looks up the value of an already evaluated gate.
+fn wire(wires: &[bool], gate: u32) -> Result<bool, ComputeError> {
+    wires.get(gate as usize).copied().ok_or_else(|| {
+        ComputeError::CircuitBuild(format!(
+            "gate {} is used before it is evaluated (only {} gates so far)",
+            gate,
+            wires.len()
+        ))
+    })
 }
@@ -1,16 +1,17 @@
 use std::fmt::Debug;
+use std::sync::Arc;
 
-use anyhow::Result;
+use crate::error::ComputeError;
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use tandem::states::Contributor;
 use tandem::Circuit;
 
 pub trait Garbler {
-    fn start(circuit: &Circuit, input: &[bool]) -> Result<(Self, Vec<u8>)>
+    fn start(circuit: &Arc<Circuit>, input: &[bool]) -> Result<(Self, Vec<u8>), ComputeError>
     where
         Self: Sized;
-    fn next(self, message: &[u8]) -> Result<(Self, Vec<u8>)>
+    fn next(self, message: &[u8]) -> Result<(Self, Vec<u8>), ComputeError>
     where
         Self: Sized;
     fn steps(&self) -> u32;
@@ -18,26 +19,20 @@ pub trait Garbler {
 }
 
 pub struct GatewayGarbler {
-    contributor: Contributor<Circuit, Vec<bool>>,
+    contributor: Contributor<Arc<Circuit>, Vec<bool>>,
     steps_remaining: u32,
 }
 
 impl Garbler for GatewayGarbler {
-    fn start(circuit: &Circuit, input: &[bool]) -> Result<(Self, Vec<u8>)> {
-        let (contributor, message) =
-            Contributor::new(circuit.clone(), input.to_vec(), ChaCha20Rng::from_entropy())?;
-        let steps_remaining = contributor.steps();
-        Ok((
-            GatewayGarbler {
-                contributor,
-                steps_remaining,
-            },
-            message,
-        ))
+    fn start(circuit: &Arc<Circuit>, input: &[bool]) -> Result<(Self, Vec<u8>), ComputeError> {
+        GatewayGarbler::start_with_rng(circuit.clone(), input, ChaCha20Rng::from_entropy())
     }
 
-    fn next(self, message: &[u8]) -> Result<(Self, Vec<u8>)> {
-        let (next_state, response) = self.contributor.run(message)?;
+    fn next(self, message: &[u8]) -> Result<(Self, Vec<u8>), ComputeError> {
+        let (next_state, response) = self
+            .contributor
+            .run(message)
+            .map_err(ComputeError::protocol)?;
         let steps_remaining = self.steps_remaining - 1;
         Ok((
             GatewayGarbler {
@@ -57,6 +52,41 @@ impl Garbler for GatewayGarbler {
     }
 }
 
+impl GatewayGarbler {
+    /// Like [`Garbler::start`], drawing all randomness from a ChaCha20 generator seeded with
+    /// `seed`, so that runs with the same seeds and inputs send the same messages.
+    ///
+    /// Only for tests and debugging: the security of the protocol depends on the garbler's
+    /// randomness being secret and never reused.
+    #[cfg(feature = "deterministic")]
+    pub fn start_seeded(
+        circuit: &Arc<Circuit>,
+        input: &[bool],
+        seed: [u8; 32],
+    ) -> Result<(Self, Vec<u8>), ComputeError> {
+        GatewayGarbler::start_with_rng(circuit.clone(), input, ChaCha20Rng::from_seed(seed))
+    }
+
+    /// Like [`Garbler::start`] for a circuit shared with other instances, drawing all randomness
+    /// from `rng`.
+    pub(crate) fn start_with_rng(
+        circuit: Arc<Circuit>,
+        input: &[bool],
+        rng: ChaCha20Rng,
+    ) -> Result<(Self, Vec<u8>), ComputeError> {
+        let (contributor, message) =
+            Contributor::new(circuit, input.to_vec(), rng).map_err(ComputeError::protocol)?;
+        let steps_remaining = contributor.steps();
+        Ok((
+            GatewayGarbler {
+                contributor,
+                steps_remaining,
+            },
+            message,
+        ))
+    }
+}
+
 impl Debug for GatewayGarbler {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("GatewayGarbler")
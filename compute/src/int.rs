@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use crate::error::ComputeError;
 use crate::uint::GarbledUint;
 use std::convert::From;
 use std::fmt::Display;
@@ -42,7 +43,8 @@ impl<const N: usize> Display for GarbledInt<N> {
 
 // Implement GarbledInt<N>
 impl<const N: usize> GarbledInt<N> {
-    // Constructor for GarbledInt<N> from a boolean vector
+    // Constructor for GarbledInt<N> from a boolean vector of N bits.
+    // Panics unless there are exactly N bits, see `try_new` for untrusted input
     pub fn new(bits: Vec<bool>) -> Self {
         assert_eq!(bits.len(), N, "The number of bits must be {}", N);
         GarbledInt {
@@ -50,6 +52,12 @@ impl<const N: usize> GarbledInt<N> {
             _phantom: PhantomData,
         }
     }
+
+    // Like `new`, returning an error instead of panicking if there aren't N bits
+    pub fn try_new(bits: Vec<bool>) -> Result<Self, ComputeError> {
+        ComputeError::check_length(N, bits.len())?;
+        Ok(GarbledInt::new(bits))
+    }
 }
 
 impl<const N: usize> From<GarbledUint<N>> for GarbledInt<N> {
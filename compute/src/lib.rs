@@ -1,19 +1,35 @@
+pub mod batch;
+#[cfg(feature = "checkpoint")]
+pub mod checkpoint;
+pub mod error;
 pub mod evaluator;
 pub mod executor;
 pub mod garbler;
 pub mod int;
+pub mod network;
 pub mod operations;
+pub mod protocol;
+pub mod report;
+pub mod transcript;
+pub mod transport;
 pub mod uint;
 
 pub mod prelude {
-    pub use crate::operations::circuits::builder::WRK17CircuitBuilder;
+    pub use crate::operations::artifact::{
+        ArtifactCache, CircuitArtifact, FunctionSignature, InputLayout, Party,
+    };
+    pub use crate::operations::circuits::builder::{GateIndex, WRK17CircuitBuilder};
 
-    pub use crate::executor::get_executor;
+    pub use crate::error::ComputeError;
+    pub use crate::executor::{
+        get_executor, set_executor, with_executor, Executor, PlaintextExecutor, SharedExecutor,
+    };
     pub use crate::int::{
         GarbledInt, GarbledInt128, GarbledInt16, GarbledInt256, GarbledInt32, GarbledInt512,
         GarbledInt64, GarbledInt8,
     };
     pub use crate::operations::circuits::types::GateIndexVec;
+    pub use crate::report::ExecutionReport;
     pub use crate::uint::{
         GarbledBoolean, GarbledUint, GarbledUint128, GarbledUint16, GarbledUint2, GarbledUint256,
         GarbledUint32, GarbledUint4, GarbledUint512, GarbledUint64, GarbledUint8,
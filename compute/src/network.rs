@@ -0,0 +1,199 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Upper bound on the number of retransmissions simulated for a single message, so that a loss
+/// rate close to 1 can't stall the simulation.
+const MAX_RETRANSMITS: u32 = 16;
+
+/// Describes the network between the two parties of the protocol.
+///
+/// The one-way delay of a message is half the round-trip time, plus its transfer time at the
+/// configured bandwidth, plus a uniformly distributed jitter. Each attempt to deliver a message is
+/// lost with probability `loss_rate`, in which case the sender waits for `retransmit_timeout`
+/// and sends it again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkProfile {
+    /// Round-trip time between the parties.
+    pub rtt: Duration,
+    /// Bandwidth in bytes per second.
+    pub bandwidth: f64,
+    /// Maximum extra delay added to each message.
+    pub jitter: Duration,
+    /// Probability, between 0 and 1, that an attempt to deliver a message is lost.
+    pub loss_rate: f64,
+    /// Time until a lost message is sent again.
+    pub retransmit_timeout: Duration,
+}
+
+impl NetworkProfile {
+    /// A network without latency, bandwidth limit or losses.
+    pub fn ideal() -> Self {
+        NetworkProfile {
+            rtt: Duration::ZERO,
+            bandwidth: f64::INFINITY,
+            jitter: Duration::ZERO,
+            loss_rate: 0.0,
+            retransmit_timeout: Duration::ZERO,
+        }
+    }
+
+    /// A network that only limits the bandwidth, given in bytes per second.
+    pub fn with_bandwidth(bandwidth: f64) -> Self {
+        NetworkProfile {
+            bandwidth,
+            ..NetworkProfile::ideal()
+        }
+    }
+
+    /// Both parties in the same data center.
+    pub fn lan() -> Self {
+        NetworkProfile {
+            rtt: Duration::from_micros(500),
+            bandwidth: 1024.0 * 1024.0 * 1024.0 / 8.0,
+            jitter: Duration::from_micros(100),
+            loss_rate: 0.0,
+            retransmit_timeout: Duration::from_millis(10),
+        }
+    }
+
+    /// Parties in different regions of the same continent.
+    pub fn regional() -> Self {
+        NetworkProfile {
+            rtt: Duration::from_millis(30),
+            bandwidth: 100.0 * 1024.0 * 1024.0 / 8.0,
+            jitter: Duration::from_millis(2),
+            loss_rate: 0.001,
+            retransmit_timeout: Duration::from_millis(200),
+        }
+    }
+
+    /// Parties on different continents.
+    pub fn intercontinental() -> Self {
+        NetworkProfile {
+            rtt: Duration::from_millis(150),
+            bandwidth: 50.0 * 1024.0 * 1024.0 / 8.0,
+            jitter: Duration::from_millis(10),
+            loss_rate: 0.01,
+            retransmit_timeout: Duration::from_millis(400),
+        }
+    }
+
+    pub fn rtt(mut self, rtt: Duration) -> Self {
+        self.rtt = rtt;
+        self
+    }
+
+    pub fn bandwidth(mut self, bandwidth: f64) -> Self {
+        self.bandwidth = bandwidth;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn loss(mut self, loss_rate: f64, retransmit_timeout: Duration) -> Self {
+        self.loss_rate = loss_rate;
+        self.retransmit_timeout = retransmit_timeout;
+        self
+    }
+
+    /// Time until a message of `bytes` bytes has arrived at the other party.
+    pub fn message_delay(&self, bytes: usize, rng: &mut impl Rng) -> Duration {
+        let transfer = if self.bandwidth.is_finite() && self.bandwidth > 0.0 {
+            Duration::from_secs_f64(bytes as f64 / self.bandwidth)
+        } else {
+            Duration::ZERO
+        };
+
+        let mut delay = self.rtt / 2 + transfer;
+        if !self.jitter.is_zero() {
+            delay += self.jitter.mul_f64(rng.gen::<f64>());
+        }
+
+        let mut retransmits = 0;
+        while retransmits < MAX_RETRANSMITS && rng.gen::<f64>() < self.loss_rate {
+            delay += self.retransmit_timeout + transfer;
+            retransmits += 1;
+        }
+        delay
+    }
+}
+
+/// Whether the simulator actually waits for simulated network delays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SimulationMode {
+    /// Sleep for the delay of each message, so the execution takes as long as it would on the
+    /// simulated network.
+    #[default]
+    Sleep,
+    /// Only add up the delays and report them, without sleeping.
+    Record,
+}
+
+/// The network time of a simulated execution.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetworkEstimate {
+    /// Number of messages exchanged between the parties.
+    pub messages: usize,
+    /// Total size of all messages in bytes.
+    pub bytes: usize,
+    /// Sum of the simulated delays of all messages.
+    pub network_time: Duration,
+    /// Time spent computing the protocol steps locally.
+    pub compute_time: Duration,
+}
+
+impl NetworkEstimate {
+    /// Estimated wall time of the execution on the simulated network. Messages are sent one
+    /// after another, so this is the computation plus the delay of every message.
+    pub fn wall_time(&self) -> Duration {
+        self.network_time + self.compute_time
+    }
+
+    pub(crate) fn record(&mut self, bytes: usize, delay: Duration) {
+        self.messages += 1;
+        self.bytes += bytes;
+        self.network_time += delay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_message_delay_without_randomness() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let profile = NetworkProfile::ideal()
+            .rtt(Duration::from_millis(40))
+            .bandwidth(1000.0);
+
+        assert_eq!(
+            profile.message_delay(500, &mut rng),
+            Duration::from_millis(520)
+        );
+        assert_eq!(
+            NetworkProfile::ideal().message_delay(1 << 20, &mut rng),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_message_delay_jitter_and_loss() {
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        let jittery = NetworkProfile::ideal().jitter(Duration::from_millis(10));
+        for _ in 0..100 {
+            assert!(jittery.message_delay(0, &mut rng) <= Duration::from_millis(10));
+        }
+
+        let lossy = NetworkProfile::ideal().loss(1.0, Duration::from_millis(100));
+        assert_eq!(
+            lossy.message_delay(0, &mut rng),
+            Duration::from_millis(100) * MAX_RETRANSMITS
+        );
+    }
+}
@@ -1,7 +1,10 @@
+use crate::error::ComputeError;
 use crate::int::GarbledInt;
 use crate::operations::circuits::builder::{
     build_and_execute_addition, build_and_execute_division, build_and_execute_multiplication,
-    build_and_execute_subtraction,
+    build_and_execute_subtraction, try_build_and_execute_addition, try_build_and_execute_division,
+    try_build_and_execute_multiplication, try_build_and_execute_remainder,
+    try_build_and_execute_subtraction,
 };
 use crate::uint::GarbledUint;
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Sub, SubAssign};
@@ -305,3 +308,50 @@ impl<const N: usize> RemAssign<&GarbledInt<N>> for GarbledInt<N> {
         *self = build_and_execute_remainder(&self.clone().into(), &rhs.into()).into();
     }
 }
+
+// Fallible arithmetic for GarbledUint<N>: the operators panic if the circuit can't be executed,
+// these return the error instead
+impl<const N: usize> GarbledUint<N> {
+    pub fn try_add(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_addition(self, rhs)
+    }
+
+    pub fn try_sub(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_subtraction(self, rhs)
+    }
+
+    pub fn try_mul(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_multiplication(self, rhs)
+    }
+
+    pub fn try_div(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_division(self, rhs)
+    }
+
+    pub fn try_rem(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_remainder(self, rhs)
+    }
+}
+
+// Fallible arithmetic for GarbledInt<N>
+impl<const N: usize> GarbledInt<N> {
+    pub fn try_add(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_addition(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_sub(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_subtraction(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_mul(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_multiplication(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_div(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_division(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_rem(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_remainder(&self.into(), &rhs.into()).map(Into::into)
+    }
+}
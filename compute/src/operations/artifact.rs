@@ -0,0 +1,871 @@
+use crate::error::ComputeError;
+use crate::executor::Executor;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use bincode::Options;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tandem::Circuit;
+use tandem::Gate;
+use tandem::GateIndex;
+
+/// Magic bytes at the start of every serialized circuit artifact.
+pub const ARTIFACT_MAGIC: [u8; 4] = *b"GWCA";
+
+/// Version of the circuit artifact format written by this crate.
+pub const ARTIFACT_VERSION: u32 = 1;
+
+/// Largest serialized artifact [`CircuitArtifact::from_bytes`] and [`CircuitArtifact::load`]
+/// accept, so that a crafted file can't make them allocate without bound.
+pub const MAX_ARTIFACT_BYTES: u64 = 1 << 30;
+
+// wrapper Gate
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum GateW {
+    /// A single input bit coming from the circuit contributor.
+    InContrib,
+    /// A single input bit coming from the circuit evaluator.
+    InEval,
+    /// A gate computing the XOR of the two specified gates.
+    Xor(GateIndex, GateIndex),
+    /// A gate computing the AND of the two specified gates.
+    And(GateIndex, GateIndex),
+    /// A gate computing the NOT of the specified gate.
+    Not(GateIndex),
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<Gate> for GateW {
+    fn into(self) -> Gate {
+        match self {
+            GateW::InContrib => Gate::InContrib,
+            GateW::InEval => Gate::InEval,
+            GateW::Xor(a, b) => Gate::Xor(a, b),
+            GateW::And(a, b) => Gate::And(a, b),
+            GateW::Not(a) => Gate::Not(a),
+        }
+    }
+}
+
+impl From<Gate> for GateW {
+    fn from(gate: Gate) -> Self {
+        match gate {
+            Gate::InContrib => GateW::InContrib,
+            Gate::InEval => GateW::InEval,
+            Gate::Xor(a, b) => GateW::Xor(a, b),
+            Gate::And(a, b) => GateW::And(a, b),
+            Gate::Not(a) => GateW::Not(a),
+        }
+    }
+}
+
+// Assuming `Gate` and `GateIndex` implement `Serialize` and `Deserialize`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CircuitWrapper {
+    gates: Vec<GateW>,
+    output_gates: Vec<GateIndex>,
+    and_gates: usize,
+    eval_inputs: usize,
+    contrib_inputs: usize,
+}
+
+// Implement conversions from `Circuit` to `CircuitWrapper` and vice versa
+impl From<&Circuit> for CircuitWrapper {
+    fn from(circuit: &Circuit) -> Self {
+        CircuitWrapper {
+            gates: circuit
+                .gates()
+                .iter()
+                .map(|gate| gate.clone().into())
+                .collect(),
+            output_gates: circuit.output_gates().clone(),
+            and_gates: circuit.and_gates(),
+            eval_inputs: circuit.eval_inputs(),
+            contrib_inputs: circuit.contrib_inputs(),
+        }
+    }
+}
+
+impl CircuitWrapper {
+    /// Checks that every gate only reads gates before it and that the outputs are gates of the
+    /// circuit, which a deserialized circuit doesn't guarantee.
+    fn validate(&self) -> Result<()> {
+        for (index, gate) in self.gates.iter().enumerate() {
+            let inputs = match *gate {
+                GateW::InContrib | GateW::InEval => vec![],
+                GateW::Xor(a, b) | GateW::And(a, b) => vec![a, b],
+                GateW::Not(a) => vec![a],
+            };
+            for input in inputs {
+                ensure!(
+                    (input as usize) < index,
+                    "gate {} reads gate {}, which is not computed before it",
+                    index,
+                    input
+                );
+            }
+        }
+        for &output in &self.output_gates {
+            ensure!(
+                (output as usize) < self.gates.len(),
+                "output gate {} is out of range, the circuit has {} gates",
+                output,
+                self.gates.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<Circuit> for CircuitWrapper {
+    fn into(self) -> Circuit {
+        Circuit::new(
+            self.gates.iter().map(|gate| gate.clone().into()).collect(),
+            self.output_gates,
+        )
+    }
+}
+
+pub fn serialize_circuit(circuit: &Circuit) -> anyhow::Result<Vec<u8>> {
+    // Convert `Circuit` to `CircuitWrapper`
+    let wrapper: CircuitWrapper = circuit.into();
+
+    // Serialize `CircuitWrapper` using bincode
+    let serialized_data = bincode::serialize(&wrapper)?;
+    Ok(serialized_data)
+}
+
+pub fn deserialize_circuit(data: &[u8]) -> anyhow::Result<Circuit> {
+    // Deserialize into `CircuitWrapper`
+    let wrapper: CircuitWrapper = bincode_options(data.len() as u64).deserialize(data)?;
+    wrapper.validate()?;
+
+    // Convert `CircuitWrapper` back into `Circuit`
+    let circuit: Circuit = wrapper.into();
+    Ok(circuit)
+}
+
+/// The party of the 2PC protocol that provides an input bit.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Party {
+    /// The garbler, which feeds the `InContrib` gates of the circuit.
+    Garbler,
+    /// The evaluator, which feeds the `InEval` gates of the circuit.
+    Evaluator,
+}
+
+/// A parameter of an encrypted function and the input bits it occupies.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InputParameter {
+    /// The parameter name as written in the function signature.
+    pub name: String,
+    /// The Rust type of the parameter, e.g. `u8`.
+    pub ty: String,
+    /// The party providing the parameter.
+    pub party: Party,
+    /// Offset of the first bit within the inputs of `party`.
+    pub offset: usize,
+    /// Number of bits, least-significant bit first.
+    pub width: usize,
+}
+
+impl InputParameter {
+    /// Fails with [`ComputeError::ParameterLength`] unless `bits` is the width of the parameter.
+    fn check(&self, bits: usize) -> Result<(), ComputeError> {
+        if bits == self.width {
+            return Ok(());
+        }
+        Err(ComputeError::ParameterLength {
+            name: self.name.clone(),
+            ty: self.ty.clone(),
+            expected: self.width,
+            actual: bits,
+        })
+    }
+}
+
+/// Input bits whose value is fixed when the circuit is compiled, such as literals in the
+/// function body. The macro feeds them to the circuit as regular garbler inputs.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConstantInput {
+    /// The party providing the bits.
+    pub party: Party,
+    /// Offset of the first bit within the inputs of `party`.
+    pub offset: usize,
+    /// The fixed bit values.
+    pub bits: Vec<bool>,
+}
+
+/// Describes which input bits of a circuit belong to which party and parameter.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InputLayout {
+    pub parameters: Vec<InputParameter>,
+    pub constants: Vec<ConstantInput>,
+}
+
+impl InputLayout {
+    pub fn new(parameters: Vec<InputParameter>, constants: Vec<ConstantInput>) -> Self {
+        InputLayout {
+            parameters,
+            constants,
+        }
+    }
+
+    /// The layout produced by the `encrypted` macro: every parameter is a garbler input of
+    /// `width` bits, in declaration order, and the remaining garbler `inputs` are the
+    /// constants hoisted out of the function body.
+    pub fn garbler_parameters(names: &[&str], ty: &str, width: usize, inputs: &[bool]) -> Self {
+        let parameters = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| InputParameter {
+                name: name.to_string(),
+                ty: ty.to_string(),
+                party: Party::Garbler,
+                offset: i * width,
+                width,
+            })
+            .collect();
+
+        let offset = (names.len() * width).min(inputs.len());
+        let constants = if offset < inputs.len() {
+            vec![ConstantInput {
+                party: Party::Garbler,
+                offset,
+                bits: inputs[offset..].to_vec(),
+            }]
+        } else {
+            vec![]
+        };
+
+        InputLayout::new(parameters, constants)
+    }
+
+    /// Looks up a parameter by name.
+    pub fn parameter(&self, name: &str) -> Option<&InputParameter> {
+        self.parameters.iter().find(|param| param.name == name)
+    }
+
+    /// Returns the parameters provided by `party`, in declaration order.
+    pub fn parameters_of(&self, party: Party) -> impl Iterator<Item = &InputParameter> {
+        self.parameters
+            .iter()
+            .filter(move |param| param.party == party)
+    }
+
+    /// Total number of input bits provided by `party`.
+    pub fn width(&self, party: Party) -> usize {
+        let parameters = self
+            .parameters_of(party)
+            .map(|param| param.offset + param.width);
+        let constants = self
+            .constants
+            .iter()
+            .filter(|constant| constant.party == party)
+            .map(|constant| constant.offset + constant.bits.len());
+        parameters.chain(constants).max().unwrap_or(0)
+    }
+
+    /// Assembles the full input vector of `party` from its parameter values, given in
+    /// declaration order, filling in the constant bits.
+    pub fn bind(&self, party: Party, arguments: &[&[bool]]) -> Result<Vec<bool>, ComputeError> {
+        let parameters: Vec<&InputParameter> = self.parameters_of(party).collect();
+        if parameters.len() != arguments.len() {
+            return Err(ComputeError::ArgumentCount {
+                party,
+                expected: parameters.len(),
+                actual: arguments.len(),
+            });
+        }
+
+        let mut input = vec![false; self.width(party)];
+        for constant in self.constants.iter().filter(|c| c.party == party) {
+            input[constant.offset..constant.offset + constant.bits.len()]
+                .copy_from_slice(&constant.bits);
+        }
+        for (param, bits) in parameters.into_iter().zip(arguments) {
+            param.check(bits.len())?;
+            input[param.offset..param.offset + param.width].copy_from_slice(bits);
+        }
+        Ok(input)
+    }
+
+    /// Checks that `input` is a full input vector of `party`. If it is too short, the error
+    /// names the parameter it ends in, which is usually the one that was encoded wrongly.
+    pub fn check(&self, party: Party, input: &[bool]) -> Result<(), ComputeError> {
+        let expected = self.width(party);
+        if input.len() == expected {
+            return Ok(());
+        }
+
+        let truncated = self
+            .parameters_of(party)
+            .filter(|param| param.offset + param.width > input.len())
+            .min_by_key(|param| param.offset);
+        match truncated {
+            Some(param) => param.check(input.len().saturating_sub(param.offset)),
+            None => Err(ComputeError::InputLength {
+                expected,
+                actual: input.len(),
+            }),
+        }
+    }
+
+    /// Checks that the parameters and constants of `party` cover each of its `expected`
+    /// input bits exactly once.
+    fn validate(&self, party: Party, expected: usize) -> Result<()> {
+        let mut covered = vec![false; expected];
+        let parameters = self
+            .parameters_of(party)
+            .map(|param| (param.name.as_str(), param.offset, param.width));
+        let constants = self
+            .constants
+            .iter()
+            .filter(|constant| constant.party == party)
+            .map(|constant| ("<constant>", constant.offset, constant.bits.len()));
+
+        for (name, offset, width) in parameters.chain(constants) {
+            ensure!(
+                offset + width <= expected,
+                "input `{}` ends at bit {} but the circuit has {} {:?} inputs",
+                name,
+                offset + width,
+                expected,
+                party
+            );
+            for bit in &mut covered[offset..offset + width] {
+                ensure!(!*bit, "input `{}` overlaps another {:?} input", name, party);
+                *bit = true;
+            }
+        }
+
+        if let Some(missing) = covered.iter().position(|bit| !bit) {
+            bail!(
+                "{:?} input bit {} is not described by the layout",
+                party,
+                missing
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The name and return type of the function a circuit was compiled from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FunctionSignature {
+    pub name: String,
+    /// The Rust return type, e.g. `u8`.
+    pub output: String,
+}
+
+impl FunctionSignature {
+    pub fn new(name: impl Into<String>, output: impl Into<String>) -> Self {
+        FunctionSignature {
+            name: name.into(),
+            output: output.into(),
+        }
+    }
+}
+
+/// A compiled circuit bundled with everything needed to run it on another host: the function
+/// signature, the input layout of both parties and a blake3 hash over all of it.
+///
+/// Both parties can compare [`CircuitArtifact::blake3_hash`] to make sure they agree on the
+/// circuit and on how inputs are fed into it before starting the protocol.
+#[derive(Clone)]
+pub struct CircuitArtifact {
+    signature: FunctionSignature,
+    layout: InputLayout,
+    circuit: Arc<Circuit>,
+    blake3_hash: [u8; 32],
+}
+
+/// The serialized form of an artifact, following the magic bytes and the format version.
+#[derive(Serialize, Deserialize)]
+struct ArtifactBody {
+    signature: FunctionSignature,
+    layout: InputLayout,
+    circuit: CircuitWrapper,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArtifactFile {
+    body: ArtifactBody,
+    blake3_hash: [u8; 32],
+}
+
+impl CircuitArtifact {
+    /// Creates an artifact, checking that `layout` accounts for every input bit of `circuit`.
+    /// Fails with [`ComputeError::CircuitBuild`] otherwise.
+    pub fn new(
+        signature: FunctionSignature,
+        layout: InputLayout,
+        circuit: Circuit,
+    ) -> Result<Self, ComputeError> {
+        layout
+            .validate(Party::Garbler, circuit.contrib_inputs())
+            .map_err(invalid)?;
+        layout
+            .validate(Party::Evaluator, circuit.eval_inputs())
+            .map_err(invalid)?;
+
+        let body = ArtifactBody {
+            signature,
+            layout,
+            circuit: (&circuit).into(),
+        };
+        let blake3_hash = hash_body(&body).map_err(invalid)?;
+
+        Ok(CircuitArtifact {
+            signature: body.signature,
+            layout: body.layout,
+            circuit: Arc::new(circuit),
+            blake3_hash,
+        })
+    }
+
+    pub fn signature(&self) -> &FunctionSignature {
+        &self.signature
+    }
+
+    pub fn layout(&self) -> &InputLayout {
+        &self.layout
+    }
+
+    pub fn circuit(&self) -> &Arc<Circuit> {
+        &self.circuit
+    }
+
+    /// The circuit, shared with the artifact instead of copied.
+    pub fn shared_circuit(&self) -> Arc<Circuit> {
+        self.circuit.clone()
+    }
+
+    /// Executes the circuit on `executor` after checking both parties' inputs against the
+    /// layout, so that a wrongly encoded input fails with the parameter it belongs to.
+    pub fn execute(
+        &self,
+        executor: &dyn Executor,
+        input_garbler: &[bool],
+        input_evaluator: &[bool],
+    ) -> Result<Vec<bool>, ComputeError> {
+        self.layout.check(Party::Garbler, input_garbler)?;
+        self.layout.check(Party::Evaluator, input_evaluator)?;
+        executor.execute(&self.circuit, input_garbler, input_evaluator)
+    }
+
+    /// The blake3 hash over the format version, signature, input layout and circuit.
+    pub fn blake3_hash(&self) -> [u8; 32] {
+        self.blake3_hash
+    }
+
+    /// Fails with [`ComputeError::CircuitBuild`] unless this artifact has the `expected` hash,
+    /// e.g. the one announced by the peer.
+    pub fn verify(&self, expected: &[u8; 32]) -> Result<(), ComputeError> {
+        if &self.blake3_hash != expected {
+            return Err(ComputeError::CircuitBuild(format!(
+                "circuit hash mismatch for `{}`: expected {}, found {}",
+                self.signature.name,
+                hex::encode(expected),
+                hex::encode(self.blake3_hash)
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let file = ArtifactFile {
+            body: ArtifactBody {
+                signature: self.signature.clone(),
+                layout: self.layout.clone(),
+                circuit: self.circuit.as_ref().into(),
+            },
+            blake3_hash: self.blake3_hash,
+        };
+
+        let mut data = ARTIFACT_MAGIC.to_vec();
+        data.extend_from_slice(&ARTIFACT_VERSION.to_le_bytes());
+        data.extend(bincode::serialize(&file)?);
+        Ok(data)
+    }
+
+    /// Parses an artifact, rejecting unknown format versions, artifacts whose contents do not
+    /// match their embedded hash and malformed circuits with [`ComputeError::CircuitBuild`].
+    /// The hash only detects corruption, so artifacts from untrusted sources are validated like
+    /// any other input.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ComputeError> {
+        Self::parse(data).map_err(invalid)
+    }
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        ensure!(
+            data.len() >= 8 && data[..4] == ARTIFACT_MAGIC,
+            "not a circuit artifact"
+        );
+        ensure!(
+            data.len() as u64 <= MAX_ARTIFACT_BYTES,
+            "circuit artifact of {} bytes exceeds the limit of {} bytes",
+            data.len(),
+            MAX_ARTIFACT_BYTES
+        );
+        let version = u32::from_le_bytes(data[4..8].try_into()?);
+        ensure!(
+            version == ARTIFACT_VERSION,
+            "unsupported circuit artifact version {} (expected {})",
+            version,
+            ARTIFACT_VERSION
+        );
+
+        // the data can't describe more than it contains, which bounds every allocation
+        let file: ArtifactFile = bincode_options(data.len() as u64).deserialize(&data[8..])?;
+        let blake3_hash = hash_body(&file.body)?;
+        ensure!(
+            blake3_hash == file.blake3_hash,
+            "circuit artifact `{}` is corrupted: hash mismatch",
+            file.body.signature.name
+        );
+        file.body.circuit.validate().with_context(|| {
+            format!("invalid circuit in artifact `{}`", file.body.signature.name)
+        })?;
+
+        let layout = file.body.layout;
+        let circuit: Circuit = file.body.circuit.into();
+        layout.validate(Party::Garbler, circuit.contrib_inputs())?;
+        layout.validate(Party::Evaluator, circuit.eval_inputs())?;
+
+        Ok(CircuitArtifact {
+            signature: file.body.signature,
+            layout,
+            circuit: Arc::new(circuit),
+            blake3_hash,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_bytes()?)
+            .map_err(|e| anyhow!("failed to write artifact {}: {}", path.display(), e))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let size = std::fs::metadata(path)
+            .map_err(|e| anyhow!("failed to read artifact {}: {}", path.display(), e))?
+            .len();
+        ensure!(
+            size <= MAX_ARTIFACT_BYTES,
+            "artifact {} of {} bytes exceeds the limit of {} bytes",
+            path.display(),
+            size,
+            MAX_ARTIFACT_BYTES
+        );
+        let data = std::fs::read(path)
+            .map_err(|e| anyhow!("failed to read artifact {}: {}", path.display(), e))?;
+        Ok(Self::from_bytes(&data)?)
+    }
+}
+
+impl Debug for CircuitArtifact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CircuitArtifact")
+            .field("signature", &self.signature)
+            .field("layout", &self.layout)
+            .field("gates", &self.circuit.gates().len())
+            .field("blake3_hash", &hex::encode(self.blake3_hash))
+            .finish()
+    }
+}
+
+/// Compiled artifacts of a single encrypted function, keyed by bit width.
+///
+/// `#[encrypted(artifact)]` keeps one of these in a `static`, so the circuit of each
+/// monomorphization is only built the first time it is requested.
+#[derive(Default)]
+pub struct ArtifactCache {
+    artifacts: OnceCell<Mutex<HashMap<usize, Arc<CircuitArtifact>>>>,
+}
+
+impl ArtifactCache {
+    pub const fn new() -> Self {
+        ArtifactCache {
+            artifacts: OnceCell::new(),
+        }
+    }
+
+    /// Returns the artifact cached for `bits`, building it with `build` on first use.
+    pub fn get_or_build<F>(&self, bits: usize, build: F) -> Arc<CircuitArtifact>
+    where
+        F: FnOnce() -> CircuitArtifact,
+    {
+        let built = self.try_get_or_build(bits, || Ok::<_, std::convert::Infallible>(build()));
+        match built {
+            Ok(artifact) => artifact,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Like [`ArtifactCache::get_or_build`], for a `build` that can fail. Nothing is cached when
+    /// it fails, so the next call builds the artifact again.
+    pub fn try_get_or_build<F, E>(&self, bits: usize, build: F) -> Result<Arc<CircuitArtifact>, E>
+    where
+        F: FnOnce() -> Result<CircuitArtifact, E>,
+    {
+        let mut artifacts = self
+            .artifacts
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(artifact) = artifacts.get(&bits) {
+            return Ok(artifact.clone());
+        }
+        let artifact = Arc::new(build()?);
+        artifacts.insert(bits, artifact.clone());
+        Ok(artifact)
+    }
+}
+
+/// The encoding of `bincode::serialize`, reading at most `limit` bytes.
+fn bincode_options(limit: u64) -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit)
+}
+
+/// Reports a malformed artifact, keeping the causes in the message.
+fn invalid(error: anyhow::Error) -> ComputeError {
+    ComputeError::CircuitBuild(format!("{error:#}"))
+}
+
+fn hash_body(body: &ArtifactBody) -> Result<[u8; 32]> {
+    let data = bincode::serialize(&(ARTIFACT_VERSION, body))?;
+    Ok(blake3::hash(&data).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[encrypted(compile)]
+    fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
+        let res = a * b;
+        let res = res + c;
+        res - d
+    }
+
+    fn addition_artifact() -> Result<CircuitArtifact> {
+        let mut builder = WRK17CircuitBuilder::default();
+        let a = builder.input::<8>(&0_u8.into());
+        let b = builder.input::<8>(&0_u8.into());
+        let output = builder.add(&a, &b);
+        let circuit = builder.compile(&output);
+
+        let parameter = |name: &str, offset| InputParameter {
+            name: name.to_string(),
+            ty: "u8".to_string(),
+            party: Party::Garbler,
+            offset,
+            width: 8,
+        };
+        let layout = InputLayout::new(vec![parameter("a", 0), parameter("b", 8)], vec![]);
+
+        Ok(CircuitArtifact::new(
+            FunctionSignature::new("addition", "u8"),
+            layout,
+            circuit,
+        )?)
+    }
+
+    #[test]
+    fn test_serialize_deserialize_circuit_struct() -> anyhow::Result<()> {
+        // Initialize the evaluator instance with circuit and dummy input
+        let (circuit, _) = multi_arithmetic(0_u8, 0_u8, 0_u8, 0_u8);
+
+        // Serialize the circuit
+        let serialized_data = serialize_circuit(&circuit)?;
+
+        // Deserialize back into a `Circuit` struct
+        let deserialized_circuit = deserialize_circuit(&serialized_data)?;
+
+        // Check if the deserialized circuit is the same as the original circuit
+        assert_eq!(circuit.gates(), deserialized_circuit.gates());
+        assert_eq!(circuit.output_gates(), deserialized_circuit.output_gates());
+        assert_eq!(circuit.and_gates(), deserialized_circuit.and_gates());
+        assert_eq!(circuit.eval_inputs(), deserialized_circuit.eval_inputs());
+        assert_eq!(
+            circuit.contrib_inputs(),
+            deserialized_circuit.contrib_inputs()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_artifact_roundtrip() -> anyhow::Result<()> {
+        let artifact = addition_artifact()?;
+
+        let data = artifact.to_bytes()?;
+        let loaded = CircuitArtifact::from_bytes(&data)?;
+
+        assert_eq!(loaded.signature(), artifact.signature());
+        assert_eq!(loaded.layout(), artifact.layout());
+        assert_eq!(loaded.circuit().gates(), artifact.circuit().gates());
+        assert_eq!(
+            loaded.circuit().output_gates(),
+            artifact.circuit().output_gates()
+        );
+        loaded.verify(&artifact.blake3_hash())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_artifact_rejects_tampering() -> anyhow::Result<()> {
+        let artifact = addition_artifact()?;
+        let mut data = artifact.to_bytes()?;
+
+        // flip a bit in the serialized circuit, just before the hash
+        let last = data.len() - 33;
+        data[last] ^= 1;
+        assert!(CircuitArtifact::from_bytes(&data).is_err());
+
+        let mut data = artifact.to_bytes()?;
+        data[4] = 99;
+        assert!(CircuitArtifact::from_bytes(&data).is_err());
+
+        assert!(artifact.verify(&[0; 32]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_artifact_rejects_malformed_circuits() -> anyhow::Result<()> {
+        let artifact = addition_artifact()?;
+        let craft = |gates: Vec<GateW>, output_gates: Vec<tandem::GateIndex>| -> Result<Vec<u8>> {
+            // a crafted file carries a matching hash, it isn't a secret
+            let body = ArtifactBody {
+                signature: artifact.signature().clone(),
+                layout: InputLayout::new(vec![], vec![]),
+                circuit: CircuitWrapper {
+                    gates,
+                    output_gates,
+                    and_gates: 0,
+                    eval_inputs: 0,
+                    contrib_inputs: 0,
+                },
+            };
+            let blake3_hash = hash_body(&body)?;
+            let mut data = ARTIFACT_MAGIC.to_vec();
+            data.extend_from_slice(&ARTIFACT_VERSION.to_le_bytes());
+            data.extend(bincode::serialize(&ArtifactFile { body, blake3_hash })?);
+            Ok(data)
+        };
+
+        let forward = craft(vec![GateW::Not(1), GateW::Not(0)], vec![1])?;
+        let error = CircuitArtifact::from_bytes(&forward).unwrap_err();
+        assert!(matches!(error, ComputeError::CircuitBuild(_)), "{error}");
+        assert!(
+            format!("{error:#}").contains("gate 0 reads gate 1"),
+            "{error:#}"
+        );
+
+        let output = craft(vec![GateW::Not(0)], vec![7]);
+        assert!(CircuitArtifact::from_bytes(&output?).is_err());
+
+        // a length prefix far beyond the end of the data fails instead of allocating
+        let mut huge = ARTIFACT_MAGIC.to_vec();
+        huge.extend_from_slice(&ARTIFACT_VERSION.to_le_bytes());
+        huge.extend_from_slice(&u64::MAX.to_le_bytes());
+        assert!(CircuitArtifact::from_bytes(&huge).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_artifact_rejects_incomplete_layout() {
+        let mut builder = WRK17CircuitBuilder::default();
+        let a = builder.input::<8>(&0_u8.into());
+        let circuit = builder.compile(&a);
+
+        let layout = InputLayout::new(
+            vec![InputParameter {
+                name: "a".to_string(),
+                ty: "u8".to_string(),
+                party: Party::Garbler,
+                offset: 0,
+                width: 4,
+            }],
+            vec![],
+        );
+        let result =
+            CircuitArtifact::new(FunctionSignature::new("identity", "u8"), layout, circuit);
+        assert!(matches!(result, Err(ComputeError::CircuitBuild(_))));
+    }
+
+    #[test]
+    fn test_layout_bind() -> anyhow::Result<()> {
+        let layout = InputLayout::new(
+            vec![InputParameter {
+                name: "a".to_string(),
+                ty: "u8".to_string(),
+                party: Party::Garbler,
+                offset: 0,
+                width: 2,
+            }],
+            vec![ConstantInput {
+                party: Party::Garbler,
+                offset: 2,
+                bits: vec![true, false],
+            }],
+        );
+
+        let input = layout.bind(Party::Garbler, &[&[false, true]])?;
+        assert_eq!(input, vec![false, true, true, false]);
+
+        assert!(layout.bind(Party::Garbler, &[&[true]]).is_err());
+        assert!(layout.bind(Party::Garbler, &[]).is_err());
+        assert_eq!(layout.bind(Party::Evaluator, &[])?, Vec::<bool>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_layout_check_names_the_parameter() {
+        let layout = addition_artifact().unwrap().layout().clone();
+
+        assert!(layout.check(Party::Garbler, &[false; 16]).is_ok());
+        assert!(matches!(
+            layout.check(Party::Garbler, &[false; 13]),
+            Err(ComputeError::ParameterLength { ref name, expected: 8, actual: 5, .. })
+                if name == "b"
+        ));
+        assert!(matches!(
+            layout.check(Party::Garbler, &[false; 17]),
+            Err(ComputeError::InputLength {
+                expected: 16,
+                actual: 17
+            })
+        ));
+        assert!(matches!(
+            layout.bind(Party::Garbler, &[&[false; 8], &[false; 7]]),
+            Err(ComputeError::ParameterLength { ref name, actual: 7, .. }) if name == "b"
+        ));
+    }
+
+    #[test]
+    fn test_artifact_execute_checks_layout() -> anyhow::Result<()> {
+        let artifact = addition_artifact()?;
+        let executor = PlaintextExecutor::new();
+
+        let input = artifact
+            .layout()
+            .bind(Party::Garbler, &[&[true; 8], &[true; 8]])?;
+        let output = artifact.execute(&executor, &input, &[])?;
+        assert_eq!(u8::from(GarbledUint8::new(output)), 254);
+
+        let error = artifact.execute(&executor, &input[..4], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "parameter `a: u8` expects 8 bits, got 4");
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
+use crate::error::ComputeError;
 use crate::int::GarbledInt;
 use crate::operations::circuits::builder::{
     build_and_execute_and, build_and_execute_nand, build_and_execute_nor, build_and_execute_not,
     build_and_execute_or, build_and_execute_shl, build_and_execute_shr, build_and_execute_xnor,
-    build_and_execute_xor,
+    build_and_execute_xor, try_build_and_execute_and, try_build_and_execute_nand,
+    try_build_and_execute_nor, try_build_and_execute_not, try_build_and_execute_or,
+    try_build_and_execute_shl, try_build_and_execute_shr, try_build_and_execute_xnor,
+    try_build_and_execute_xor,
 };
 use crate::uint::GarbledUint;
 use std::ops::{
@@ -467,3 +471,82 @@ impl<const N: usize> GarbledInt<N> {
         build_and_execute_xnor(&self.into(), &rhs.into()).into()
     }
 }
+
+// Fallible bitwise operations for GarbledUint<N>: the operators panic if the circuit can't be
+// executed, these return the error instead
+impl<const N: usize> GarbledUint<N> {
+    pub fn try_and(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_and(self, rhs)
+    }
+
+    pub fn try_or(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_or(self, rhs)
+    }
+
+    pub fn try_xor(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_xor(self, rhs)
+    }
+
+    pub fn try_nand(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_nand(self, rhs)
+    }
+
+    pub fn try_nor(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_nor(self, rhs)
+    }
+
+    pub fn try_xnor(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_xnor(self, rhs)
+    }
+
+    pub fn try_not(&self) -> Result<Self, ComputeError> {
+        try_build_and_execute_not(self)
+    }
+
+    pub fn try_shl<const K: usize>(&self, rhs: &GarbledUint<K>) -> Result<Self, ComputeError> {
+        try_build_and_execute_shl(self, rhs)
+    }
+
+    pub fn try_shr<const K: usize>(&self, rhs: &GarbledUint<K>) -> Result<Self, ComputeError> {
+        try_build_and_execute_shr(self, rhs)
+    }
+}
+
+// Fallible bitwise operations for GarbledInt<N>
+impl<const N: usize> GarbledInt<N> {
+    pub fn try_and(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_and(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_or(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_or(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_xor(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_xor(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_nand(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_nand(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_nor(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_nor(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_xnor(&self, rhs: &Self) -> Result<Self, ComputeError> {
+        try_build_and_execute_xnor(&self.into(), &rhs.into()).map(Into::into)
+    }
+
+    pub fn try_not(&self) -> Result<Self, ComputeError> {
+        try_build_and_execute_not(&self.into()).map(Into::into)
+    }
+
+    pub fn try_shl<const K: usize>(&self, rhs: &GarbledUint<K>) -> Result<Self, ComputeError> {
+        try_build_and_execute_shl(&self.into(), rhs).map(Into::into)
+    }
+
+    pub fn try_shr<const K: usize>(&self, rhs: &GarbledUint<K>) -> Result<Self, ComputeError> {
+        try_build_and_execute_shr(&self.into(), rhs).map(Into::into)
+    }
+}
@@ -1,11 +1,14 @@
+use crate::error::ComputeError;
+use crate::executor::{get_executor, Executor};
 use crate::operations::circuits::traits::CircuitExecutor;
 use crate::operations::circuits::types::GateIndexVec;
+use crate::uint::GarbledBoolean;
 use crate::uint::GarbledUint;
-use crate::{executor::get_executor, uint::GarbledBoolean};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::Arc;
 use tandem::{Circuit, Gate};
 
 pub type GateIndex = u32;
@@ -221,19 +224,32 @@ impl WRK17CircuitBuilder {
         Circuit::new(self.gates.clone(), output_indices.clone().into())
     }
 
-    pub fn execute<const N: usize>(&self, circuit: &Circuit) -> anyhow::Result<GarbledUint<N>> {
-        let result = get_executor().execute(circuit, &self.inputs, &[])?;
-        Ok(GarbledUint::new(result))
+    pub fn execute<const N: usize>(
+        &self,
+        circuit: &Circuit,
+    ) -> Result<GarbledUint<N>, ComputeError> {
+        self.execute_with(circuit, get_executor().as_ref())
+    }
+
+    // Execute the circuit on the given executor instead of the current one. This copies the
+    // circuit into an `Arc`; pass a shared circuit to `Executor::execute` to avoid the copy
+    pub fn execute_with<const N: usize>(
+        &self,
+        circuit: &Circuit,
+        executor: &dyn Executor,
+    ) -> Result<GarbledUint<N>, ComputeError> {
+        let result = executor.execute(&Arc::new(circuit.clone()), &self.inputs, &[])?;
+        GarbledUint::try_new(result)
     }
 
     // Simulate the circuit using the provided input values
     pub fn compile_and_execute<const N: usize>(
         &self,
         output_indices: &GateIndexVec,
-    ) -> anyhow::Result<GarbledUint<N>> {
-        let circuit = self.compile(output_indices);
+    ) -> Result<GarbledUint<N>, ComputeError> {
+        let circuit = Arc::new(self.compile(output_indices));
         let result = get_executor().execute(&circuit, &self.inputs, &[])?;
-        Ok(GarbledUint::new(result))
+        GarbledUint::try_new(result)
     }
 }
 
@@ -472,15 +488,23 @@ impl CircuitExecutor for WRK17CircuitBuilder {
     }
 }
 
+/// Checks that an operand of an operation on `N`-bit integers has `N` bits.
+fn check_operand<const N: usize>(operand: &GarbledUint<N>) -> Result<(), ComputeError> {
+    if N == 0 {
+        return Err(ComputeError::UnsupportedWidth(N));
+    }
+    ComputeError::check_length(N, operand.len())
+}
+
 macro_rules! build_and_execute {
-    ($fn_name:ident, $op:ident) => {
-        pub(crate) fn $fn_name<const N: usize>(
+    ($fn_name:ident, $try_fn_name:ident, $op:ident) => {
+        pub(crate) fn $try_fn_name<const N: usize>(
             lhs: &GarbledUint<N>,
             rhs: &GarbledUint<N>,
-        ) -> GarbledUint<N> {
+        ) -> Result<GarbledUint<N>, ComputeError> {
+            check_operand(lhs)?;
+            check_operand(rhs)?;
             let mut builder = WRK17CircuitBuilder::default();
-            // Access the global CircuitBuilder instance
-            //let mut builder = WRK17CircuitBuilder::instance().lock().unwrap();
 
             let a = builder.input(lhs);
             let b = builder.input(rhs);
@@ -488,32 +512,58 @@ macro_rules! build_and_execute {
             let output = builder.$op(&a, &b);
             let circuit = builder.compile(&output);
 
-            // Execute the circuit
-            builder
-                .execute(&circuit)
-                .expect("Failed to execute circuit")
+            builder.execute(&circuit)
+        }
+
+        pub(crate) fn $fn_name<const N: usize>(
+            lhs: &GarbledUint<N>,
+            rhs: &GarbledUint<N>,
+        ) -> GarbledUint<N> {
+            $try_fn_name(lhs, rhs).expect("Failed to execute circuit")
         }
     };
 }
 
-build_and_execute!(build_and_execute_xor, xor);
-build_and_execute!(build_and_execute_and, and);
-build_and_execute!(build_and_execute_or, or);
-build_and_execute!(build_and_execute_nand, nand);
-build_and_execute!(build_and_execute_nor, nor);
-build_and_execute!(build_and_execute_xnor, xnor);
-build_and_execute!(build_and_execute_addition, add);
-build_and_execute!(build_and_execute_subtraction, sub);
-build_and_execute!(build_and_execute_multiplication, mul);
-build_and_execute!(build_and_execute_division, div);
-build_and_execute!(build_and_execute_remainder, rem);
+build_and_execute!(build_and_execute_xor, try_build_and_execute_xor, xor);
+build_and_execute!(build_and_execute_and, try_build_and_execute_and, and);
+build_and_execute!(build_and_execute_or, try_build_and_execute_or, or);
+build_and_execute!(build_and_execute_nand, try_build_and_execute_nand, nand);
+build_and_execute!(build_and_execute_nor, try_build_and_execute_nor, nor);
+build_and_execute!(build_and_execute_xnor, try_build_and_execute_xnor, xnor);
+build_and_execute!(
+    build_and_execute_addition,
+    try_build_and_execute_addition,
+    add
+);
+build_and_execute!(
+    build_and_execute_subtraction,
+    try_build_and_execute_subtraction,
+    sub
+);
+build_and_execute!(
+    build_and_execute_multiplication,
+    try_build_and_execute_multiplication,
+    mul
+);
+build_and_execute!(
+    build_and_execute_division,
+    try_build_and_execute_division,
+    div
+);
+build_and_execute!(
+    build_and_execute_remainder,
+    try_build_and_execute_remainder,
+    rem
+);
 
 macro_rules! build_and_execute_shift {
-    ($fn_name:ident, $op:ident) => {
-        pub(crate) fn $fn_name<const N: usize, const K: usize>(
+    ($fn_name:ident, $try_fn_name:ident, $op:ident) => {
+        pub(crate) fn $try_fn_name<const N: usize, const K: usize>(
             lhs: &GarbledUint<N>,
             rhs: &GarbledUint<K>,
-        ) -> GarbledUint<N> {
+        ) -> Result<GarbledUint<N>, ComputeError> {
+            check_operand(lhs)?;
+            check_operand(rhs)?;
             let mut builder = WRK17CircuitBuilder::default();
 
             let a = builder.input(lhs);
@@ -522,13 +572,20 @@ macro_rules! build_and_execute_shift {
             let output = builder.$op(&a, &b);
             let circuit = builder.compile(&output);
 
-            builder.execute(&circuit).expect("Circuit execution failed")
+            builder.execute(&circuit)
+        }
+
+        pub(crate) fn $fn_name<const N: usize, const K: usize>(
+            lhs: &GarbledUint<N>,
+            rhs: &GarbledUint<K>,
+        ) -> GarbledUint<N> {
+            $try_fn_name(lhs, rhs).expect("Circuit execution failed")
         }
     };
 }
 
-build_and_execute_shift!(build_and_execute_shl, shl);
-build_and_execute_shift!(build_and_execute_shr, shr);
+build_and_execute_shift!(build_and_execute_shl, try_build_and_execute_shl, shl);
+build_and_execute_shift!(build_and_execute_shr, try_build_and_execute_shr, shr);
 
 fn full_adder(
     builder: &mut WRK17CircuitBuilder,
@@ -624,48 +681,65 @@ fn partial_product_shift(
     shifted
 }
 
-pub(crate) fn build_and_execute_equality<const N: usize>(
+pub(crate) fn try_build_and_execute_equality<const N: usize>(
     lhs: &GarbledUint<N>,
     rhs: &GarbledUint<N>,
-) -> bool {
+) -> Result<bool, ComputeError> {
+    check_operand(lhs)?;
+    check_operand(rhs)?;
     let mut builder = WRK17CircuitBuilder::default();
     let a = builder.input(lhs);
     let b = builder.input(rhs);
 
     let result = builder.eq(&a, &b);
-    let result = builder
-        .compile_and_execute::<1>(&vec![result].into())
-        .expect("Failed to execute equality circuit");
-    result.into()
+    let result = builder.compile_and_execute::<1>(&vec![result].into())?;
+    Ok(result.into())
 }
 
-pub(crate) fn build_and_execute_comparator<const N: usize>(
+pub(crate) fn build_and_execute_equality<const N: usize>(
     lhs: &GarbledUint<N>,
     rhs: &GarbledUint<N>,
-) -> Ordering {
+) -> bool {
+    try_build_and_execute_equality(lhs, rhs).expect("Failed to execute equality circuit")
+}
+
+pub(crate) fn try_build_and_execute_comparator<const N: usize>(
+    lhs: &GarbledUint<N>,
+    rhs: &GarbledUint<N>,
+) -> Result<Ordering, ComputeError> {
+    check_operand(lhs)?;
+    check_operand(rhs)?;
     let mut builder = WRK17CircuitBuilder::default();
     let a = builder.input(lhs);
     let b = builder.input(rhs);
 
     let (lt_output, eq_output) = builder.compare(&a, &b);
 
-    let result = builder
-        .compile_and_execute::<2>(&vec![lt_output, eq_output].into())
-        .expect("Failed to execute equality circuit");
+    let result = builder.compile_and_execute::<2>(&vec![lt_output, eq_output].into())?;
 
     let lt = result.bits[0];
     let eq = result.bits[1];
 
     if lt {
-        Ordering::Less
+        Ok(Ordering::Less)
     } else if eq {
-        Ordering::Equal
+        Ok(Ordering::Equal)
     } else {
-        Ordering::Greater
+        Ok(Ordering::Greater)
     }
 }
 
-pub(crate) fn build_and_execute_not<const N: usize>(input: &GarbledUint<N>) -> GarbledUint<N> {
+pub(crate) fn build_and_execute_comparator<const N: usize>(
+    lhs: &GarbledUint<N>,
+    rhs: &GarbledUint<N>,
+) -> Ordering {
+    try_build_and_execute_comparator(lhs, rhs).expect("Failed to execute equality circuit")
+}
+
+pub(crate) fn try_build_and_execute_not<const N: usize>(
+    input: &GarbledUint<N>,
+) -> Result<GarbledUint<N>, ComputeError> {
+    check_operand(input)?;
     let mut builder = WRK17CircuitBuilder::default();
     builder.input(input);
 
@@ -677,37 +751,37 @@ pub(crate) fn build_and_execute_not<const N: usize>(input: &GarbledUint<N>) -> G
         output_indices.push(not_gate);
     }
 
-    builder
-        .compile_and_execute(&output_indices)
-        .expect("Failed to execute a.len()OT circuit")
+    builder.compile_and_execute(&output_indices)
 }
 
-pub(crate) fn build_and_execute_mux<const N: usize>(
+pub(crate) fn build_and_execute_not<const N: usize>(input: &GarbledUint<N>) -> GarbledUint<N> {
+    try_build_and_execute_not(input).expect("Failed to execute NOT circuit")
+}
+
+pub(crate) fn try_build_and_execute_mux<const N: usize>(
     condition: &GarbledBoolean,
     if_true: &GarbledUint<N>,
     if_false: &GarbledUint<N>,
-) -> GarbledUint<N> {
+) -> Result<GarbledUint<N>, ComputeError> {
+    check_operand(condition)?;
+    check_operand(if_true)?;
+    check_operand(if_false)?;
     let mut builder = WRK17CircuitBuilder::default();
     let a = builder.input(if_true);
     let b = builder.input(if_false);
     let s = builder.input(condition);
 
-    // Add MUX gates for each bit
-    /*
-    let mut output_indices = Vec::with_capacity(a.len());
-    let n = a.len() as u32;
-    for i in 0..n {
-        let mux_gate = builder.push_mux(&i, &(n + i), &(2 * n));
-        output_indices.push(mux_gate);
-    }
-    */
-
     let output = builder.mux(&s[0], &a, &b);
 
-    // Simulate the circuit
-    builder
-        .compile_and_execute(&output)
-        .expect("Failed to execute MUX circuit")
+    builder.compile_and_execute(&output)
+}
+
+pub(crate) fn build_and_execute_mux<const N: usize>(
+    condition: &GarbledBoolean,
+    if_true: &GarbledUint<N>,
+    if_false: &GarbledUint<N>,
+) -> GarbledUint<N> {
+    try_build_and_execute_mux(condition, if_true, if_false).expect("Failed to execute MUX circuit")
 }
 
 // tests
@@ -965,6 +1039,30 @@ mod tests {
         assert_eq!(result_value, 9 * 3);
     }
 
+    #[test]
+    fn test_execute_rejects_output_width_mismatch() {
+        let a: GarbledUint8 = 9_u8.into();
+        let b: GarbledUint8 = 3_u8.into();
+
+        let mut builder = WRK17CircuitBuilder::default();
+        let a = builder.input(&a);
+        let b = builder.input(&b);
+        let output = builder.mul(&a, &b);
+
+        let circuit = builder.compile(&output);
+        let result = builder.execute::<16>(&circuit);
+        assert!(matches!(
+            result,
+            Err(ComputeError::InputLength {
+                expected: 16,
+                actual: 8
+            })
+        ));
+
+        let result = builder.compile_and_execute::<16>(&output);
+        assert!(matches!(result, Err(ComputeError::InputLength { .. })));
+    }
+
     #[test]
     fn test_build_and_execute_shl() {
         let a: GarbledUint8 = 31_u8.into();
@@ -1,3 +1,4 @@
+use crate::error::ComputeError;
 use crate::operations::circuits::builder::GateIndex;
 use crate::uint::GarbledBoolean;
 use serde::{Deserialize, Serialize};
@@ -49,6 +50,13 @@ impl GateIndexVec {
     pub fn set(&mut self, index: usize, value: GateIndex) {
         self.0[index] = value;
     }
+
+    /// The least significant wire, e.g. the result of a comparison or the condition of a MUX.
+    pub fn lsb(&self) -> Result<GateIndex, ComputeError> {
+        self.0.first().copied().ok_or_else(|| {
+            ComputeError::CircuitBuild("expected at least one wire, got none".to_string())
+        })
+    }
 }
 
 // Implement indexing for GateVector
@@ -72,9 +80,11 @@ impl From<Vec<GateIndex>> for GateIndexVec {
     }
 }
 
-impl From<GateIndexVec> for GarbledBoolean {
-    fn from(vec: GateIndexVec) -> Self {
-        GarbledBoolean::from(vec.0[0])
+impl TryFrom<GateIndexVec> for GarbledBoolean {
+    type Error = ComputeError;
+
+    fn try_from(vec: GateIndexVec) -> Result<Self, Self::Error> {
+        Ok(GarbledBoolean::from(vec.lsb()?))
     }
 }
 
@@ -106,22 +116,21 @@ impl From<&GateIndexVec> for GateIndexVec {
     }
 }
 
-impl From<GateIndexVec> for GateIndex {
-    fn from(vec: GateIndexVec) -> Self {
-        vec.0[0]
-    }
-}
+// The wire of a single-bit value, e.g. the condition of a MUX generated by `#[encrypted]`. Fails
+// on an empty vector, see `GateIndexVec::lsb`
+impl TryFrom<GateIndexVec> for GateIndex {
+    type Error = ComputeError;
 
-impl From<&GateIndexVec> for GateIndex {
-    fn from(vec: &GateIndexVec) -> Self {
-        vec.0[0]
+    fn try_from(vec: GateIndexVec) -> Result<Self, Self::Error> {
+        vec.lsb()
     }
 }
 
-#[allow(clippy::from_over_into)]
-impl<'a> Into<&'a GateIndex> for &'a GateIndexVec {
-    fn into(self) -> &'a GateIndex {
-        &self.0[0]
+impl TryFrom<&GateIndexVec> for GateIndex {
+    type Error = ComputeError;
+
+    fn try_from(vec: &GateIndexVec) -> Result<Self, Self::Error> {
+        vec.lsb()
     }
 }
 
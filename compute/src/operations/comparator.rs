@@ -1,6 +1,8 @@
+use crate::error::ComputeError;
 use crate::int::GarbledInt;
 use crate::operations::circuits::builder::{
-    build_and_execute_comparator, build_and_execute_equality,
+    build_and_execute_comparator, build_and_execute_equality, try_build_and_execute_comparator,
+    try_build_and_execute_equality,
 };
 use crate::uint::GarbledUint;
 use std::cmp::Ordering;
@@ -77,3 +79,26 @@ impl<const N: usize> Ord for GarbledInt<N> {
         build_and_execute_comparator(&self.into(), &other.into())
     }
 }
+
+// Fallible comparisons for GarbledUint<N>: the comparison operators panic if the circuit can't
+// be executed, these return the error instead
+impl<const N: usize> GarbledUint<N> {
+    pub fn try_eq(&self, other: &Self) -> Result<bool, ComputeError> {
+        try_build_and_execute_equality(self, other)
+    }
+
+    pub fn try_cmp(&self, other: &Self) -> Result<Ordering, ComputeError> {
+        try_build_and_execute_comparator(self, other)
+    }
+}
+
+// Fallible comparisons for GarbledInt<N>
+impl<const N: usize> GarbledInt<N> {
+    pub fn try_eq(&self, other: &Self) -> Result<bool, ComputeError> {
+        try_build_and_execute_equality(&self.into(), &other.into())
+    }
+
+    pub fn try_cmp(&self, other: &Self) -> Result<Ordering, ComputeError> {
+        try_build_and_execute_comparator(&self.into(), &other.into())
+    }
+}
@@ -1,4 +1,5 @@
 pub mod arithmetic;
+pub mod artifact;
 pub mod bitwise;
 pub mod circuits;
 pub mod comparator;
@@ -1,5 +1,6 @@
+use crate::error::ComputeError;
 use crate::int::GarbledInt;
-use crate::operations::circuits::builder::build_and_execute_mux;
+use crate::operations::circuits::builder::{build_and_execute_mux, try_build_and_execute_mux};
 use crate::uint::GarbledBoolean;
 use crate::uint::GarbledUint;
 
@@ -12,6 +13,15 @@ impl<const N: usize> GarbledUint<N> {
     ) -> GarbledUint<N> {
         build_and_execute_mux(condition, if_true, if_false)
     }
+
+    // MUX that returns the error instead of panicking if the circuit can't be executed
+    pub fn try_mux(
+        condition: &GarbledBoolean,
+        if_true: &GarbledUint<N>,
+        if_false: &GarbledUint<N>,
+    ) -> Result<GarbledUint<N>, ComputeError> {
+        try_build_and_execute_mux(condition, if_true, if_false)
+    }
 }
 
 impl<const N: usize> GarbledInt<N> {
@@ -23,4 +33,13 @@ impl<const N: usize> GarbledInt<N> {
     ) -> GarbledInt<N> {
         build_and_execute_mux(condition, &if_true.into(), &if_false.into()).into()
     }
+
+    // MUX that returns the error instead of panicking if the circuit can't be executed
+    pub fn try_mux(
+        condition: &GarbledBoolean,
+        if_true: &GarbledInt<N>,
+        if_false: &GarbledInt<N>,
+    ) -> Result<GarbledInt<N>, ComputeError> {
+        try_build_and_execute_mux(condition, &if_true.into(), &if_false.into()).map(Into::into)
+    }
 }
@@ -0,0 +1,97 @@
+//! Async drivers running one party of the protocol over a [`Transport`].
+//!
+//! The garbler sends the first message, after which both parties alternate until the garbler's
+//! steps are complete. The evaluator then computes the output and sends it back to the garbler,
+//! one byte per bit, so that both parties learn the result.
+//!
+//! Every protocol step runs on tokio's blocking thread pool, so a server can drive many sessions
+//! concurrently without stalling the runtime while garbling or evaluating.
+
+use crate::evaluator::{Evaluator, GatewayEvaluator};
+use crate::garbler::{Garbler, GatewayGarbler};
+use crate::transport::Transport;
+use anyhow::{bail, Context, Result};
+use std::sync::Arc;
+use tandem::Circuit;
+use tokio::task::spawn_blocking;
+use tracing::debug;
+
+/// Runs the garbler side of the protocol with `input` and returns the output of the circuit.
+pub async fn run_garbler<T>(
+    transport: &mut T,
+    circuit: &Arc<Circuit>,
+    input: &[bool],
+) -> Result<Vec<bool>>
+where
+    T: Transport + ?Sized,
+{
+    let (circuit, input) = (circuit.clone(), input.to_vec());
+    let (mut garbler, message) =
+        spawn_blocking(move || GatewayGarbler::start(&circuit, &input)).await??;
+    transport.send(message).await?;
+
+    while !garbler.is_complete() {
+        let message = transport
+            .receive()
+            .await
+            .context("waiting for the evaluator")?;
+        let (next_garbler, reply) = spawn_blocking(move || garbler.next(&message)).await??;
+        debug!(steps_remaining = next_garbler.steps(), "garbler step");
+        garbler = next_garbler;
+        transport.send(reply).await?;
+    }
+
+    let output = transport
+        .receive()
+        .await
+        .context("waiting for the output")?;
+    decode_output(&output)
+}
+
+/// Runs the evaluator side of the protocol with `input`, sends the output of the circuit to the
+/// garbler and returns it.
+pub async fn run_evaluator<T>(
+    transport: &mut T,
+    circuit: &Arc<Circuit>,
+    input: &[bool],
+) -> Result<Vec<bool>>
+where
+    T: Transport + ?Sized,
+{
+    let (circuit, input) = (circuit.clone(), input.to_vec());
+    let mut evaluator = spawn_blocking(move || GatewayEvaluator::new(&circuit, &input)).await??;
+
+    loop {
+        let message = transport
+            .receive()
+            .await
+            .context("waiting for the garbler")?;
+
+        if evaluator.is_complete() {
+            let output = spawn_blocking(move || evaluator.output(&message)).await??;
+            transport.send(encode_output(&output)).await?;
+            return Ok(output);
+        }
+
+        let (next_evaluator, reply) = spawn_blocking(move || evaluator.next(&message)).await??;
+        debug!(steps_remaining = next_evaluator.steps(), "evaluator step");
+        evaluator = next_evaluator;
+        transport.send(reply).await?;
+    }
+}
+
+/// Encodes output bits as the evaluator sends them to the garbler: one byte per bit.
+pub fn encode_output(output: &[bool]) -> Vec<u8> {
+    output.iter().map(|&bit| bit as u8).collect()
+}
+
+/// Decodes the output sent by the evaluator, see [`encode_output`].
+pub fn decode_output(data: &[u8]) -> Result<Vec<bool>> {
+    data.iter()
+        .map(|&byte| match byte {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => bail!("malformed output from the evaluator: invalid bit {}", other),
+        })
+        .collect()
+}
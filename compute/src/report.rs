@@ -0,0 +1,150 @@
+use crate::network::NetworkEstimate;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tandem::{Circuit, Gate};
+
+/// The number of gates of each kind in a circuit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GateCounts {
+    pub and: usize,
+    pub xor: usize,
+    pub not: usize,
+    pub contrib_inputs: usize,
+    pub eval_inputs: usize,
+}
+
+impl GateCounts {
+    pub fn of(circuit: &Circuit) -> Self {
+        let mut counts = GateCounts::default();
+        for gate in circuit.gates() {
+            match gate {
+                Gate::InContrib => counts.contrib_inputs += 1,
+                Gate::InEval => counts.eval_inputs += 1,
+                Gate::Xor(_, _) => counts.xor += 1,
+                Gate::And(_, _) => counts.and += 1,
+                Gate::Not(_) => counts.not += 1,
+            }
+        }
+        counts
+    }
+
+    /// Total number of gates, including input gates.
+    pub fn total(&self) -> usize {
+        self.and + self.xor + self.not + self.contrib_inputs + self.eval_inputs
+    }
+}
+
+/// The direction in which a protocol message is sent.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    GarblerToEvaluator,
+    EvaluatorToGarbler,
+}
+
+/// A single message exchanged during an execution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageStats {
+    /// The protocol step the message belongs to, starting at 0 for the garbler's first message.
+    pub step: u32,
+    pub direction: Direction,
+    pub bytes: usize,
+}
+
+/// Statistics of one execution of the protocol, returned by `Executor::execute_with_report`.
+///
+/// tandem interleaves the oblivious transfers with garbling and evaluating in the same protocol
+/// steps, so the timings are attributed by party: `garbling` is all work done by the garbler and
+/// `evaluating` all work done by the evaluator, each including its share of the oblivious
+/// transfers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExecutionReport {
+    pub gates: GateCounts,
+    /// All messages in the order they were sent. Empty for executors that don't exchange
+    /// messages, such as `PlaintextExecutor`.
+    pub messages: Vec<MessageStats>,
+    /// Number of round trips between the parties.
+    pub rounds: u32,
+    pub garbling: Duration,
+    pub evaluating: Duration,
+    /// Wall-clock time of the whole execution.
+    pub total: Duration,
+    /// The simulated network time, if the execution ran on a `LocalSimulator`.
+    pub network: Option<NetworkEstimate>,
+}
+
+impl ExecutionReport {
+    /// An empty report for an execution of `circuit`.
+    pub fn new(circuit: &Circuit) -> Self {
+        ExecutionReport {
+            gates: GateCounts::of(circuit),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn record_message(&mut self, step: u32, direction: Direction, bytes: usize) {
+        self.messages.push(MessageStats {
+            step,
+            direction,
+            bytes,
+        });
+    }
+
+    /// Total bytes sent in `direction`.
+    pub fn bytes_sent(&self, direction: Direction) -> usize {
+        self.messages
+            .iter()
+            .filter(|message| message.direction == direction)
+            .map(|message| message.bytes)
+            .sum()
+    }
+
+    /// Total bytes sent in both directions.
+    pub fn total_bytes(&self) -> usize {
+        self.messages.iter().map(|message| message.bytes).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gate_counts() {
+        let circuit = Circuit::new(
+            vec![
+                Gate::InContrib,
+                Gate::InEval,
+                Gate::Xor(0, 1),
+                Gate::And(0, 2),
+                Gate::Not(3),
+                Gate::And(3, 4),
+            ],
+            vec![5],
+        );
+
+        let counts = GateCounts::of(&circuit);
+        assert_eq!(
+            counts,
+            GateCounts {
+                and: 2,
+                xor: 1,
+                not: 1,
+                contrib_inputs: 1,
+                eval_inputs: 1,
+            }
+        );
+        assert_eq!(counts.total(), 6);
+    }
+
+    #[test]
+    fn test_bytes_per_direction() {
+        let mut report = ExecutionReport::default();
+        report.record_message(0, Direction::GarblerToEvaluator, 100);
+        report.record_message(1, Direction::EvaluatorToGarbler, 20);
+        report.record_message(1, Direction::GarblerToEvaluator, 5);
+
+        assert_eq!(report.bytes_sent(Direction::GarblerToEvaluator), 105);
+        assert_eq!(report.bytes_sent(Direction::EvaluatorToGarbler), 20);
+        assert_eq!(report.total_bytes(), 125);
+    }
+}
@@ -0,0 +1,256 @@
+//! Transcripts of the messages exchanged in a protocol run.
+//!
+//! [`LocalSimulator::record`](crate::executor::LocalSimulator::record) records the transcript of
+//! an execution, and [`LocalSimulator::replay`](crate::executor::LocalSimulator::replay) runs the
+//! execution again and checks every message against a recorded transcript, which finds the first
+//! message where two runs diverge. Both parties draw fresh randomness in every run, so a replay
+//! only matches when the simulator is seeded the same way as the recording, see
+//! `LocalSimulator::seeded` behind the `deterministic` feature.
+//!
+//! Over a network, [`TranscriptRecorder`](crate::transport::TranscriptRecorder) records the
+//! messages one party sends and receives to a file. [`replay_evaluator`] and [`replay_garbler`]
+//! feed the messages one party sent to a new instance of the other party, which reproduces
+//! failures in handling them offline. The new party draws fresh randomness, so once the messages
+//! are handled without errors its own messages differ from the recorded ones, and the output is
+//! only meaningful if the run failed before any randomness was involved.
+//!
+//! A transcript contains the output of the circuit in the clear, in the evaluator's last message,
+//! so it has to be stored as securely as the output.
+
+use crate::evaluator::{Evaluator, GatewayEvaluator};
+use crate::garbler::{Garbler, GatewayGarbler};
+use crate::protocol::decode_output;
+use crate::report::Direction;
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tandem::Circuit;
+use tracing::warn;
+
+/// Version of the serialized transcript format, bumped on incompatible changes.
+pub const FORMAT_VERSION: u16 = 1;
+
+/// One message of a transcript.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TranscriptMessage {
+    /// The protocol step the message belongs to, starting at 0 for the garbler's first message.
+    pub step: u32,
+    pub direction: Direction,
+    /// Time since the recording started.
+    pub elapsed: Duration,
+    pub data: Vec<u8>,
+}
+
+impl TranscriptMessage {
+    /// Serializes the message as one record of the transcript format, see
+    /// [`Transcript::to_bytes`].
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+}
+
+impl fmt::Debug for TranscriptMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TranscriptMessage")
+            .field("step", &self.step)
+            .field("direction", &self.direction)
+            .field("elapsed", &self.elapsed)
+            .field("bytes", &self.data.len())
+            .finish()
+    }
+}
+
+/// The messages of a protocol run, in the order they were sent.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transcript {
+    pub messages: Vec<TranscriptMessage>,
+}
+
+impl Transcript {
+    pub fn new() -> Self {
+        Transcript::default()
+    }
+
+    pub fn push(&mut self, step: u32, direction: Direction, elapsed: Duration, data: &[u8]) {
+        self.messages.push(TranscriptMessage {
+            step,
+            direction,
+            elapsed,
+            data: data.to_vec(),
+        });
+    }
+
+    /// The header of the serialized format, followed by one record per message.
+    pub fn header() -> Result<Vec<u8>> {
+        Ok(bincode::serialize(&FORMAT_VERSION)?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = Transcript::header()?;
+        for message in &self.messages {
+            data.extend(message.to_bytes()?);
+        }
+        Ok(data)
+    }
+
+    /// Deserializes a transcript written by [`Transcript::to_bytes`] or a
+    /// [`TranscriptRecorder`](crate::transport::TranscriptRecorder). A last message that was cut
+    /// off, because the recording stopped while writing it, is left out.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let mut data = data;
+        let version: u16 =
+            bincode::deserialize_from(&mut data).context("reading transcript version")?;
+        ensure!(
+            version == FORMAT_VERSION,
+            "unsupported transcript format version {version}, expected {FORMAT_VERSION}"
+        );
+
+        let mut transcript = Transcript::new();
+        while !data.is_empty() {
+            match bincode::deserialize_from(&mut data) {
+                Ok(message) => transcript.messages.push(message),
+                Err(error) => match *error {
+                    bincode::ErrorKind::Io(ref io)
+                        if io.kind() == std::io::ErrorKind::UnexpectedEof =>
+                    {
+                        warn!(
+                            messages = transcript.messages.len(),
+                            "transcript ends with an incomplete message"
+                        );
+                        break;
+                    }
+                    _ => return Err(error).context("reading transcript message"),
+                },
+            }
+        }
+        Ok(transcript)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_bytes()?)
+            .with_context(|| format!("writing transcript {}", path.display()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .with_context(|| format!("reading transcript {}", path.display()))?;
+        Transcript::from_bytes(&data).with_context(|| format!("loading {}", path.display()))
+    }
+
+    /// The messages sent in `direction`, in order.
+    pub fn sent(&self, direction: Direction) -> impl Iterator<Item = &TranscriptMessage> {
+        self.messages
+            .iter()
+            .filter(move |message| message.direction == direction)
+    }
+}
+
+/// Checks the messages of a run against a recorded transcript, in order.
+pub(crate) struct Expected<'a> {
+    messages: std::slice::Iter<'a, TranscriptMessage>,
+}
+
+impl<'a> Expected<'a> {
+    pub(crate) fn new(transcript: &'a Transcript) -> Self {
+        Expected {
+            messages: transcript.messages.iter(),
+        }
+    }
+
+    /// Fails if the next recorded message differs from the one sent in this run.
+    pub(crate) fn check(&mut self, step: u32, direction: Direction, data: &[u8]) -> Result<()> {
+        let expected = self.messages.next().with_context(|| {
+            format!("run diverged at step {step}: {direction:?} message is not in the transcript")
+        })?;
+        ensure!(
+            expected.step == step && expected.direction == direction,
+            "run diverged at step {step}: sent a {direction:?} message, but the transcript has a \
+             {:?} message of step {}",
+            expected.direction,
+            expected.step
+        );
+        if let Some(offset) = expected
+            .data
+            .iter()
+            .zip(data)
+            .position(|(expected, actual)| expected != actual)
+        {
+            bail!("run diverged at step {step}: {direction:?} message differs at byte {offset}");
+        }
+        ensure!(
+            expected.data.len() == data.len(),
+            "run diverged at step {step}: {direction:?} message has {} bytes, the transcript {}",
+            data.len(),
+            expected.data.len()
+        );
+        Ok(())
+    }
+
+    /// Fails if the transcript has messages the run didn't send.
+    pub(crate) fn finish(mut self) -> Result<()> {
+        match self.messages.next() {
+            Some(message) => bail!(
+                "run ended before the {:?} message of step {} in the transcript",
+                message.direction,
+                message.step
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Feeds the garbler's messages of `transcript` to a new evaluator of `circuit` with `input`, and
+/// returns the output it computes. Fails with the step of the first message the evaluator can't
+/// handle, see the [module docs](self) for what a replay can reproduce.
+pub fn replay_evaluator(
+    circuit: &Arc<Circuit>,
+    input: &[bool],
+    transcript: &Transcript,
+) -> Result<Vec<bool>> {
+    let mut evaluator = GatewayEvaluator::new(circuit, input)?;
+    for message in transcript.sent(Direction::GarblerToEvaluator) {
+        let step = message.step;
+        if evaluator.is_complete() {
+            return evaluator
+                .output(&message.data)
+                .with_context(|| format!("evaluating the output at step {step}"));
+        }
+        (evaluator, _) = evaluator
+            .next(&message.data)
+            .with_context(|| format!("evaluator step {step}"))?;
+    }
+    bail!(
+        "transcript ends before the garbler's last message, with {} evaluator steps remaining",
+        evaluator.steps()
+    )
+}
+
+/// Feeds the evaluator's messages of `transcript` to a new garbler of `circuit` with `input`, and
+/// returns the output the evaluator sent at the end. Fails with the step of the first message
+/// the garbler can't handle, see the [module docs](self) for what a replay can reproduce.
+pub fn replay_garbler(
+    circuit: &Arc<Circuit>,
+    input: &[bool],
+    transcript: &Transcript,
+) -> Result<Vec<bool>> {
+    let (mut garbler, _) = GatewayGarbler::start(circuit, input)?;
+    for message in transcript.sent(Direction::EvaluatorToGarbler) {
+        let step = message.step;
+        if garbler.is_complete() {
+            return decode_output(&message.data)
+                .with_context(|| format!("decoding the output at step {step}"));
+        }
+        (garbler, _) = garbler
+            .next(&message.data)
+            .with_context(|| format!("garbler step {step}"))?;
+    }
+    bail!(
+        "transcript ends before the output, with {} garbler steps remaining",
+        garbler.steps()
+    )
+}
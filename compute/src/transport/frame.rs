@@ -0,0 +1,375 @@
+//! The framing used by stream transports.
+//!
+//! Every frame starts with a 6 byte header: the format version, the frame type and the length of
+//! the payload as a little-endian `u32`, followed by the payload itself.
+//!
+//! A message is sent either as a single `Message` frame, or split into any number of `Chunk`
+//! frames followed by a `Message` frame with the rest, so that no frame is larger than the chunk
+//! size. Receivers accept both.
+
+use std::io;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Version of the frame format written by this crate.
+pub const FRAME_VERSION: u8 = 1;
+
+/// Size of the frame header in bytes.
+pub const HEADER_LEN: usize = 6;
+
+/// Default limit for the payload of a single frame: the largest length the header can carry, so
+/// that large protocol messages only fail where a lower limit is configured.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = u32::MAX as usize;
+
+/// Bytes requested from the reader at once while waiting for a frame.
+const READ_CHUNK: usize = 64 * 1024;
+
+/// What a frame carries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FrameType {
+    /// A message of the 2PC protocol.
+    Message,
+    /// A `RemoteError` explaining why the sender aborted the session.
+    Error,
+    /// A part of a message, which continues in the following frames up to a `Message` frame.
+    Chunk,
+}
+
+impl FrameType {
+    fn to_byte(self) -> u8 {
+        match self {
+            FrameType::Message => 0,
+            FrameType::Error => 1,
+            FrameType::Chunk => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(FrameType::Message),
+            1 => Some(FrameType::Error),
+            2 => Some(FrameType::Chunk),
+            _ => None,
+        }
+    }
+}
+
+/// A decoded frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub ty: FrameType,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, Error)]
+pub enum FrameError {
+    #[error("unsupported frame version {0}, expected {FRAME_VERSION}")]
+    UnsupportedVersion(u8),
+    #[error("unknown frame type {0}")]
+    UnknownType(u8),
+    #[error("frame of {size} bytes exceeds the limit of {max} bytes")]
+    TooLarge { size: usize, max: usize },
+    #[error("message of at least {size} bytes exceeds the limit of {max} bytes")]
+    MessageTooLarge { size: usize, max: usize },
+    #[error("connection closed in the middle of a frame ({buffered} bytes received)")]
+    Truncated { buffered: usize },
+    #[error("connection closed")]
+    Closed,
+    #[error("unexpected {0:?} frame")]
+    Unexpected(FrameType),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Encodes frames and decodes them from data arriving in arbitrary chunks.
+#[derive(Clone, Debug)]
+pub struct FrameCodec {
+    max_frame_size: usize,
+    buffer: Vec<u8>,
+    /// Type and length of a frame whose header was already taken off the buffer.
+    pending: Option<(FrameType, usize)>,
+}
+
+impl Default for FrameCodec {
+    fn default() -> Self {
+        FrameCodec::new(DEFAULT_MAX_FRAME_SIZE)
+    }
+}
+
+impl FrameCodec {
+    /// Creates a codec that rejects frames with a payload larger than `max_frame_size` bytes.
+    pub fn new(max_frame_size: usize) -> Self {
+        FrameCodec {
+            max_frame_size,
+            buffer: Vec::new(),
+            pending: None,
+        }
+    }
+
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Encodes `payload` as a frame of type `ty`.
+    pub fn encode(&self, ty: FrameType, payload: &[u8]) -> Result<Vec<u8>, FrameError> {
+        let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+        frame.extend_from_slice(&self.header(ty, payload.len())?);
+        frame.extend_from_slice(payload);
+        Ok(frame)
+    }
+
+    fn header(&self, ty: FrameType, length: usize) -> Result<[u8; HEADER_LEN], FrameError> {
+        self.check_size(length)?;
+        let mut header = [FRAME_VERSION, ty.to_byte(), 0, 0, 0, 0];
+        header[2..].copy_from_slice(&(length as u32).to_le_bytes());
+        Ok(header)
+    }
+
+    /// Appends received data to the internal buffer.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next frame if it has been received completely. The header is validated as
+    /// soon as it is complete, so oversized frames are rejected before their payload arrives.
+    pub fn decode(&mut self) -> Result<Option<Frame>, FrameError> {
+        let (ty, length) = match self.pending {
+            Some(pending) => pending,
+            None => {
+                if self.buffer.len() < HEADER_LEN {
+                    return Ok(None);
+                }
+                let header = self.read_header()?;
+                // the header is split off while little of the payload has arrived, so the
+                // payload itself never has to be moved
+                self.buffer.drain(..HEADER_LEN);
+                self.pending = Some(header);
+                header
+            }
+        };
+
+        if self.buffer.len() < length {
+            return Ok(None);
+        }
+        // hands the buffer over as the payload instead of copying it
+        let rest = self.buffer.split_off(length);
+        let payload = std::mem::replace(&mut self.buffer, rest);
+        self.pending = None;
+        Ok(Some(Frame { ty, payload }))
+    }
+
+    fn read_header(&self) -> Result<(FrameType, usize), FrameError> {
+        let version = self.buffer[0];
+        if version != FRAME_VERSION {
+            return Err(FrameError::UnsupportedVersion(version));
+        }
+        let ty =
+            FrameType::from_byte(self.buffer[1]).ok_or(FrameError::UnknownType(self.buffer[1]))?;
+        let length = u32::from_le_bytes([
+            self.buffer[2],
+            self.buffer[3],
+            self.buffer[4],
+            self.buffer[5],
+        ]) as usize;
+        self.check_size(length)?;
+        Ok((ty, length))
+    }
+
+    /// Checks that the stream did not end in the middle of a frame.
+    pub fn finish(&self) -> Result<(), FrameError> {
+        let header = if self.pending.is_some() {
+            HEADER_LEN
+        } else {
+            0
+        };
+        match self.buffer.len() + header {
+            0 => Ok(()),
+            buffered => Err(FrameError::Truncated { buffered }),
+        }
+    }
+
+    /// Reads from `reader` until a complete frame has been received.
+    pub async fn read_frame<R>(&mut self, reader: &mut R) -> Result<Frame, FrameError>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        loop {
+            if let Some(frame) = self.decode()? {
+                return Ok(frame);
+            }
+
+            let start = self.buffer.len();
+            self.buffer.resize(start + READ_CHUNK, 0);
+            let read = match reader.read(&mut self.buffer[start..]).await {
+                Ok(read) => read,
+                Err(err) => {
+                    self.buffer.truncate(start);
+                    return Err(err.into());
+                }
+            };
+            self.buffer.truncate(start + read);
+
+            if read == 0 {
+                self.finish()?;
+                return Err(FrameError::Closed);
+            }
+        }
+    }
+
+    /// Writes `payload` as a frame of type `ty` to `writer`, without copying it.
+    pub async fn write_frame<W>(
+        &self,
+        writer: &mut W,
+        ty: FrameType,
+        payload: &[u8],
+    ) -> Result<(), FrameError>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        writer.write_all(&self.header(ty, payload.len())?).await?;
+        writer.write_all(payload).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Writes `message` as `Chunk` frames of `chunk_size` bytes followed by a `Message` frame
+    /// with the rest. Every frame is flushed before the next one is written, so the writer's
+    /// flow control holds back the sender.
+    pub async fn write_chunked<W>(
+        &self,
+        writer: &mut W,
+        message: &[u8],
+        chunk_size: usize,
+    ) -> Result<(), FrameError>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let chunk_size = chunk_size.clamp(1, self.max_frame_size.max(1));
+        let last = message.len().saturating_sub(1) / chunk_size * chunk_size;
+        for chunk in message[..last].chunks(chunk_size) {
+            self.write_frame(writer, FrameType::Chunk, chunk).await?;
+        }
+        self.write_frame(writer, FrameType::Message, &message[last..])
+            .await
+    }
+
+    fn check_size(&self, size: usize) -> Result<(), FrameError> {
+        if size > self.max_frame_size || size > u32::MAX as usize {
+            return Err(FrameError::TooLarge {
+                size,
+                max: self.max_frame_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_byte_by_byte() -> Result<(), FrameError> {
+        let mut codec = FrameCodec::default();
+        let mut data = codec.encode(FrameType::Message, b"hello")?;
+        data.extend(codec.encode(FrameType::Message, b"")?);
+
+        let mut frames = vec![];
+        for byte in data {
+            codec.push(&[byte]);
+            while let Some(frame) = codec.decode()? {
+                frames.push(frame.payload);
+            }
+        }
+        assert_eq!(frames, vec![b"hello".to_vec(), vec![]]);
+        codec.finish()
+    }
+
+    #[test]
+    fn test_rejects_oversized_frames_from_header() {
+        let codec = FrameCodec::new(4);
+        assert!(matches!(
+            codec.encode(FrameType::Message, b"hello"),
+            Err(FrameError::TooLarge { size: 5, max: 4 })
+        ));
+
+        let mut codec = FrameCodec::new(4);
+        let header = [FRAME_VERSION, 0, 0, 0, 0, 1];
+        codec.push(&header);
+        assert!(matches!(
+            codec.decode(),
+            Err(FrameError::TooLarge { size: 16777216, .. })
+        ));
+    }
+
+    #[test]
+    fn test_default_limit_accepts_any_length() -> Result<(), FrameError> {
+        let mut codec = FrameCodec::default();
+        codec.push(&[FRAME_VERSION, 0, 0xff, 0xff, 0xff, 0xff]);
+        // waits for the payload of the largest possible frame instead of rejecting it
+        assert!(codec.decode()?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_chunked() -> Result<(), FrameError> {
+        let codec = FrameCodec::new(4);
+        let mut data = vec![];
+        codec.write_chunked(&mut data, b"0123456789", 4).await?;
+        codec.write_chunked(&mut data, b"", 4).await?;
+
+        let mut reader = &data[..];
+        let mut codec = FrameCodec::new(4);
+        let mut frames = vec![];
+        while let Ok(frame) = codec.read_frame(&mut reader).await {
+            frames.push((frame.ty, frame.payload));
+        }
+        assert_eq!(
+            frames,
+            vec![
+                (FrameType::Chunk, b"0123".to_vec()),
+                (FrameType::Chunk, b"4567".to_vec()),
+                (FrameType::Message, b"89".to_vec()),
+                (FrameType::Message, vec![]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_unknown_header() {
+        let mut codec = FrameCodec::default();
+        codec.push(&[FRAME_VERSION + 1, 0, 0, 0, 0, 0]);
+        assert!(matches!(
+            codec.decode(),
+            Err(FrameError::UnsupportedVersion(_))
+        ));
+
+        let mut codec = FrameCodec::default();
+        codec.push(&[FRAME_VERSION, 200, 0, 0, 0, 0]);
+        assert!(matches!(codec.decode(), Err(FrameError::UnknownType(200))));
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_reports_truncation() -> Result<(), FrameError> {
+        let codec = FrameCodec::default();
+        let frame = codec.encode(FrameType::Message, b"payload")?;
+
+        let mut reader = &frame[..frame.len() - 1];
+        let result = FrameCodec::default().read_frame(&mut reader).await;
+        assert!(matches!(
+            result,
+            Err(FrameError::Truncated { buffered: 12 })
+        ));
+
+        let mut reader: &[u8] = &[];
+        let result = FrameCodec::default().read_frame(&mut reader).await;
+        assert!(matches!(result, Err(FrameError::Closed)));
+
+        let mut reader = &frame[..];
+        let received = FrameCodec::default().read_frame(&mut reader).await?;
+        assert_eq!(received.payload, b"payload");
+        Ok(())
+    }
+}
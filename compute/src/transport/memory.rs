@@ -0,0 +1,57 @@
+use super::{RemoteError, Transport};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+/// Number of messages that can be in flight in each direction before `send` waits.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// One end of an in-memory channel, for running both parties in the same process.
+pub struct ChannelTransport {
+    sender: Sender<Result<Vec<u8>, RemoteError>>,
+    receiver: Receiver<Result<Vec<u8>, RemoteError>>,
+}
+
+impl ChannelTransport {
+    /// Creates two connected ends, one for each party.
+    pub fn pair() -> (ChannelTransport, ChannelTransport) {
+        let (a_sender, a_receiver) = channel(CHANNEL_CAPACITY);
+        let (b_sender, b_receiver) = channel(CHANNEL_CAPACITY);
+        (
+            ChannelTransport {
+                sender: a_sender,
+                receiver: b_receiver,
+            },
+            ChannelTransport {
+                sender: b_sender,
+                receiver: a_receiver,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl Transport for ChannelTransport {
+    async fn send(&mut self, message: Vec<u8>) -> Result<()> {
+        self.sender
+            .send(Ok(message))
+            .await
+            .map_err(|_| anyhow!("the other end of the channel was dropped"))
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        let message = self
+            .receiver
+            .recv()
+            .await
+            .ok_or_else(|| anyhow!("the other end of the channel was dropped"))?;
+        Ok(message?)
+    }
+
+    async fn abort(&mut self, error: RemoteError) -> Result<()> {
+        self.sender
+            .send(Err(error))
+            .await
+            .map_err(|_| anyhow!("the other end of the channel was dropped"))
+    }
+}
@@ -0,0 +1,119 @@
+pub mod frame;
+mod memory;
+mod quic;
+mod record;
+mod stream;
+
+pub use memory::ChannelTransport;
+pub use quic::QuicTransport;
+pub use record::TranscriptRecorder;
+pub use stream::{StreamTransport, TcpTransport};
+
+#[cfg(unix)]
+pub use stream::UnixTransport;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+/// The category of a [`RemoteError`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The session took too long or the peer stopped responding.
+    Timeout,
+    /// The peer can't take more sessions right now.
+    Busy,
+    /// The peer is shutting down and doesn't accept new sessions.
+    ShuttingDown,
+    /// The peer refused the request, e.g. an unknown function.
+    Rejected,
+    /// Any other failure on the peer's side.
+    Internal,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Busy => "busy",
+            ErrorKind::ShuttingDown => "shutting down",
+            ErrorKind::Rejected => "rejected",
+            ErrorKind::Internal => "internal error",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// An error the other party reported before aborting the session, returned by
+/// [`Transport::receive`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Error)]
+#[error("peer aborted the session ({kind}): {message}")]
+pub struct RemoteError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl RemoteError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        RemoteError {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+/// A reliable, ordered channel between the two parties of the protocol that delivers whole
+/// messages.
+///
+/// The protocol drivers in [`crate::protocol`] only talk to the other party through this trait,
+/// so the same driver can run over any network stack.
+#[async_trait]
+pub trait Transport: Send {
+    /// Sends one message to the other party.
+    async fn send(&mut self, message: Vec<u8>) -> Result<()>;
+
+    /// Receives the next message from the other party. Fails with a [`RemoteError`] if the
+    /// other party aborted the session.
+    async fn receive(&mut self) -> Result<Vec<u8>>;
+
+    /// Tells the other party why the session is aborted, so that its next `receive` fails with
+    /// `error` instead of a closed connection. Transports that can't carry errors only report
+    /// the closed connection.
+    async fn abort(&mut self, error: RemoteError) -> Result<()> {
+        let _ = error;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    async fn send(&mut self, message: Vec<u8>) -> Result<()> {
+        (**self).send(message).await
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        (**self).receive().await
+    }
+
+    async fn abort(&mut self, error: RemoteError) -> Result<()> {
+        (**self).abort(error).await
+    }
+}
+
+#[async_trait]
+impl<T: Transport + ?Sized> Transport for &mut T {
+    async fn send(&mut self, message: Vec<u8>) -> Result<()> {
+        (**self).send(message).await
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        (**self).receive().await
+    }
+
+    async fn abort(&mut self, error: RemoteError) -> Result<()> {
+        (**self).abort(error).await
+    }
+}
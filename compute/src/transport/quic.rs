@@ -0,0 +1,27 @@
+use super::StreamTransport;
+use anyhow::{Context, Result};
+use quinn::{Connection, RecvStream, SendStream};
+
+/// A transport over a bidirectional QUIC stream of a `quinn` connection.
+pub type QuicTransport = StreamTransport<RecvStream, SendStream>;
+
+impl QuicTransport {
+    /// Opens a new stream on `connection`. The peer only sees the stream once the first message
+    /// is sent, so this should be called by the garbler.
+    pub async fn open(connection: &Connection) -> Result<Self> {
+        let (send, receive) = connection
+            .open_bi()
+            .await
+            .context("opening a QUIC stream")?;
+        Ok(StreamTransport::new(receive, send))
+    }
+
+    /// Accepts the next stream opened by the peer.
+    pub async fn accept(connection: &Connection) -> Result<Self> {
+        let (send, receive) = connection
+            .accept_bi()
+            .await
+            .context("accepting a QUIC stream")?;
+        Ok(StreamTransport::new(receive, send))
+    }
+}
@@ -0,0 +1,136 @@
+use super::{RemoteError, Transport};
+use crate::operations::artifact::Party;
+use crate::report::Direction;
+use crate::transcript::{Transcript, TranscriptMessage};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::Path;
+use std::time::Instant;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tracing::warn;
+
+/// Records the messages a party sends and receives over another transport, with the time and
+/// protocol step of each, in the format of [`Transcript::to_bytes`].
+///
+/// Every message is written as soon as it is sent or received, so a transcript ends with the
+/// last message before a failure and can be read with [`Transcript::load`] even if the process
+/// crashed. Wrap the transport once the protocol starts, after any handshake, so that the steps
+/// of the transcript are the steps of the protocol. If writing the transcript fails, recording
+/// stops with a warning and the session continues.
+///
+/// See the security notes in [`crate::transcript`].
+pub struct TranscriptRecorder<T, W> {
+    inner: T,
+    writer: Option<W>,
+    party: Party,
+    started: Instant,
+    garbler_messages: u32,
+    evaluator_messages: u32,
+}
+
+impl<T> TranscriptRecorder<T, File> {
+    /// Records the messages of `party` on `inner` to a new file at `path`, which only its owner
+    /// can read.
+    pub async fn create(inner: T, party: Party, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = create_private(path)
+            .await
+            .with_context(|| format!("creating transcript {}", path.display()))?;
+        TranscriptRecorder::new(inner, party, file).await
+    }
+}
+
+/// Creates a file with mode 0600, as transcripts hold garbled tables and OT messages.
+async fn create_private(path: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let file = options.open(path).await?;
+    // `mode` only applies to new files, so also tighten a file that already existed
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))
+        .await?;
+    Ok(file)
+}
+
+impl<T, W> TranscriptRecorder<T, W>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    /// Records the messages of `party` on `inner` to `writer`.
+    pub async fn new(inner: T, party: Party, mut writer: W) -> Result<Self> {
+        writer.write_all(&Transcript::header()?).await?;
+        writer.flush().await?;
+        Ok(TranscriptRecorder {
+            inner,
+            writer: Some(writer),
+            party,
+            started: Instant::now(),
+            garbler_messages: 0,
+            evaluator_messages: 0,
+        })
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Records a message sent by the garbler if `from_garbler`, by the evaluator otherwise.
+    async fn record(&mut self, from_garbler: bool, data: Vec<u8>) {
+        // the garbler's messages start at step 0, the evaluator's replies at step 1
+        let (step, direction) = if from_garbler {
+            self.garbler_messages += 1;
+            (self.garbler_messages - 1, Direction::GarblerToEvaluator)
+        } else {
+            self.evaluator_messages += 1;
+            (self.evaluator_messages, Direction::EvaluatorToGarbler)
+        };
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+
+        let message = TranscriptMessage {
+            step,
+            direction,
+            elapsed: self.started.elapsed(),
+            data,
+        };
+        let written = async {
+            writer.write_all(&message.to_bytes()?).await?;
+            writer.flush().await?;
+            anyhow::Ok(())
+        };
+        if let Err(error) = written.await {
+            warn!(step, "stopped recording the transcript: {:#}", error);
+            self.writer = None;
+        }
+    }
+}
+
+#[async_trait]
+impl<T, W> Transport for TranscriptRecorder<T, W>
+where
+    T: Transport,
+    W: AsyncWrite + Unpin + Send,
+{
+    async fn send(&mut self, message: Vec<u8>) -> Result<()> {
+        // only messages that were sent are recorded, so that a replay doesn't expect a message
+        // the other party never received
+        self.inner.send(message.clone()).await?;
+        self.record(self.party == Party::Garbler, message).await;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        let message = self.inner.receive().await?;
+        self.record(self.party == Party::Evaluator, message.clone())
+            .await;
+        Ok(message)
+    }
+
+    async fn abort(&mut self, error: RemoteError) -> Result<()> {
+        self.inner.abort(error).await
+    }
+}
@@ -0,0 +1,184 @@
+use super::frame::{FrameCodec, FrameError, FrameType};
+use super::{RemoteError, Transport};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite, ReadHalf, WriteHalf};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+/// A transport over any byte stream, such as a TCP connection, a Unix domain socket or a QUIC
+/// stream. Messages are sent as frames, see [`super::frame`].
+///
+/// By default every message is sent as a single frame. With [`StreamTransport::with_chunk_size`]
+/// messages are split into frames of at most that size instead, which keeps the memory used by
+/// the transport proportional to the chunk size. Received messages may be chunked either way.
+pub struct StreamTransport<R, W> {
+    reader: R,
+    writer: W,
+    codec: FrameCodec,
+    chunk_size: Option<usize>,
+    max_message_size: Option<usize>,
+}
+
+impl<R, W> StreamTransport<R, W>
+where
+    R: AsyncRead + Unpin + Send,
+    W: AsyncWrite + Unpin + Send,
+{
+    /// Creates a transport from the two directions of a stream.
+    pub fn new(reader: R, writer: W) -> Self {
+        StreamTransport {
+            reader,
+            writer,
+            codec: FrameCodec::default(),
+            chunk_size: None,
+            max_message_size: None,
+        }
+    }
+
+    /// Limits the size of frames sent and received over this transport, and of messages unless
+    /// set with [`StreamTransport::with_max_message_size`].
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.codec = FrameCodec::new(max_frame_size);
+        self
+    }
+
+    /// Sends messages in frames of at most `chunk_size` bytes, waiting for each to be written
+    /// before the next.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Limits the size of messages received in chunks.
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = Some(max_message_size);
+        self
+    }
+
+    fn max_message_size(&self) -> usize {
+        self.max_message_size
+            .unwrap_or_else(|| self.codec.max_frame_size())
+    }
+
+    fn check_message_size(&self, size: usize) -> Result<(), FrameError> {
+        let max = self.max_message_size();
+        if size > max {
+            return Err(FrameError::MessageTooLarge { size, max });
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+}
+
+impl<S> StreamTransport<ReadHalf<S>, WriteHalf<S>>
+where
+    S: AsyncRead + AsyncWrite + Send,
+{
+    /// Creates a transport from a stream that can be read and written.
+    pub fn from_stream(stream: S) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        StreamTransport::new(reader, writer)
+    }
+}
+
+/// A transport over a TCP connection.
+pub type TcpTransport =
+    StreamTransport<tokio::net::tcp::OwnedReadHalf, tokio::net::tcp::OwnedWriteHalf>;
+
+impl TcpTransport {
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self> {
+        let stream = TcpStream::connect(addr)
+            .await
+            .context("connecting over TCP")?;
+        Ok(TcpTransport::from_tcp(stream))
+    }
+
+    pub fn from_tcp(stream: TcpStream) -> Self {
+        let (reader, writer) = stream.into_split();
+        StreamTransport::new(reader, writer)
+    }
+}
+
+/// A transport over a Unix domain socket.
+#[cfg(unix)]
+pub type UnixTransport =
+    StreamTransport<tokio::net::unix::OwnedReadHalf, tokio::net::unix::OwnedWriteHalf>;
+
+#[cfg(unix)]
+impl UnixTransport {
+    pub async fn connect(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let stream = tokio::net::UnixStream::connect(path)
+            .await
+            .context("connecting to the Unix domain socket")?;
+        Ok(UnixTransport::from_unix(stream))
+    }
+
+    pub fn from_unix(stream: tokio::net::UnixStream) -> Self {
+        let (reader, writer) = stream.into_split();
+        StreamTransport::new(reader, writer)
+    }
+}
+
+#[async_trait]
+impl<R, W> Transport for StreamTransport<R, W>
+where
+    R: AsyncRead + Unpin + Send,
+    W: AsyncWrite + Unpin + Send,
+{
+    async fn send(&mut self, message: Vec<u8>) -> Result<()> {
+        match self.chunk_size {
+            Some(chunk_size) => {
+                self.check_message_size(message.len())?;
+                self.codec
+                    .write_chunked(&mut self.writer, &message, chunk_size)
+                    .await?
+            }
+            None => {
+                self.codec
+                    .write_frame(&mut self.writer, FrameType::Message, &message)
+                    .await?
+            }
+        }
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        let mut message = Vec::new();
+        loop {
+            let frame = self.codec.read_frame(&mut self.reader).await?;
+            match frame.ty {
+                FrameType::Chunk | FrameType::Message => {
+                    self.check_message_size(message.len() + frame.payload.len())?;
+                    let last = frame.ty == FrameType::Message;
+                    // takes the first frame as the message instead of copying it
+                    if message.is_empty() {
+                        message = frame.payload;
+                    } else {
+                        message.extend_from_slice(&frame.payload);
+                    }
+                    if last {
+                        return Ok(message);
+                    }
+                }
+                FrameType::Error => {
+                    let error: RemoteError =
+                        bincode::deserialize(&frame.payload).context("malformed error frame")?;
+                    return Err(error.into());
+                }
+                #[allow(unreachable_patterns)]
+                other => return Err(FrameError::Unexpected(other).into()),
+            }
+        }
+    }
+
+    async fn abort(&mut self, error: RemoteError) -> Result<()> {
+        let payload = bincode::serialize(&error)?;
+        self.codec
+            .write_frame(&mut self.writer, FrameType::Error, &payload)
+            .await?;
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use crate::error::ComputeError;
 use crate::int::GarbledInt;
 use ruint::Uint;
 use std::fmt::{Display, Formatter, LowerHex};
@@ -75,14 +76,21 @@ impl<const BITS: usize> LowerHex for GarbledUint<BITS> {
 
 // Implement Uint<N>
 impl<const N: usize> GarbledUint<N> {
-    // Constructor for GarbledUint<N> from a boolean vector
+    // Constructor for GarbledUint<N> from a boolean vector of N bits, least-significant bit first.
+    // Panics unless there are exactly N bits, see `try_new` for untrusted input
     pub fn new(bits: Vec<bool>) -> Self {
-        //assert_eq!(bits.len(), N, "The number of bits must be {}", N);
+        assert_eq!(bits.len(), N, "The number of bits must be {}", N);
         GarbledUint {
             bits,
             _phantom: PhantomData,
         }
     }
+
+    // Like `new`, returning an error instead of panicking if there aren't N bits
+    pub fn try_new(bits: Vec<bool>) -> Result<Self, ComputeError> {
+        ComputeError::check_length(N, bits.len())?;
+        Ok(GarbledUint::new(bits))
+    }
 }
 
 impl<const N: usize> From<GarbledInt<N>> for GarbledUint<N> {
@@ -106,7 +114,11 @@ impl<const N: usize> From<&GarbledInt<N>> for GarbledUint<N> {
 
 impl<const N: usize> From<bool> for GarbledUint<N> {
     fn from(value: bool) -> Self {
-        GarbledUint::new(vec![value])
+        let mut bits = vec![false; N];
+        if let Some(lsb) = bits.first_mut() {
+            *lsb = value;
+        }
+        GarbledUint::new(bits)
     }
 }
 
@@ -192,7 +204,7 @@ impl<const N: usize> From<u128> for GarbledUint<N> {
 
 impl<const N: usize> From<GarbledUint<N>> for bool {
     fn from(guint: GarbledUint<N>) -> Self {
-        guint.bits[0]
+        guint.bits.first().copied().unwrap_or(false)
     }
 }
 
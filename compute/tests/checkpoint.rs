@@ -0,0 +1,119 @@
+#![cfg(feature = "checkpoint")]
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use compute::checkpoint::{run_evaluator, run_garbler, Checkpoint};
+use compute::prelude::*;
+use compute::transport::{ChannelTransport, Transport};
+use std::sync::Arc;
+
+#[encrypted(compile)]
+fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
+    let res = a * b;
+    let res = res + c;
+    res - d
+}
+
+/// Loses the connection when sending more than `sends` messages.
+struct Flaky {
+    inner: Option<ChannelTransport>,
+    sends: usize,
+}
+
+#[async_trait]
+impl Transport for Flaky {
+    async fn send(&mut self, message: Vec<u8>) -> Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) if self.sends > 0 => {
+                self.sends -= 1;
+                inner.send(message).await
+            }
+            _ => {
+                self.inner = None;
+                bail!("connection lost")
+            }
+        }
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.receive().await,
+            None => bail!("connection lost"),
+        }
+    }
+}
+
+async fn run(
+    garbler: &mut Checkpoint,
+    evaluator: &mut Checkpoint,
+    circuit: &Arc<Circuit>,
+    garbler_sends: usize,
+) -> (Result<Vec<bool>>, Result<Vec<bool>>) {
+    let (garbler_side, mut evaluator_side) = ChannelTransport::pair();
+    let mut garbler_side = Flaky {
+        inner: Some(garbler_side),
+        sends: garbler_sends,
+    };
+    tokio::join!(
+        run_garbler(&mut garbler_side, circuit, garbler),
+        run_evaluator(&mut evaluator_side, circuit, evaluator),
+    )
+}
+
+#[tokio::test]
+async fn test_checkpointed_run() -> Result<()> {
+    let (circuit, input) = multi_arithmetic(2_u8, 5_u8, 3_u8, 4_u8);
+    let mut garbler = Checkpoint::garbler(&circuit, &input)?;
+    let mut evaluator = Checkpoint::evaluator(&circuit, &[])?;
+
+    let (garbler_output, evaluator_output) =
+        run(&mut garbler, &mut evaluator, &circuit, usize::MAX).await;
+    let output = garbler_output?;
+    assert_eq!(output, evaluator_output?);
+    let result: u8 = GarbledUint::<8>::new(output).into();
+    assert_eq!(result, 2 * 5 + 3 - 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resumes_after_connection_loss() -> Result<()> {
+    let (circuit, input) = multi_arithmetic(2_u8, 5_u8, 3_u8, 4_u8);
+    let mut garbler = Checkpoint::garbler(&circuit, &input)?;
+    let mut evaluator = Checkpoint::evaluator(&circuit, &[])?;
+
+    // the connection drops after the garbler's first protocol message
+    let (garbler_output, evaluator_output) = run(&mut garbler, &mut evaluator, &circuit, 2).await;
+    assert!(garbler_output.is_err());
+    assert!(evaluator_output.is_err());
+    assert!(evaluator.received() > 0);
+
+    // the evaluator is restored from its stored checkpoint
+    let mut evaluator = Checkpoint::from_bytes(&evaluator.to_bytes()?)?;
+    let (garbler_output, evaluator_output) =
+        run(&mut garbler, &mut evaluator, &circuit, usize::MAX).await;
+    let output = garbler_output?;
+    assert_eq!(output, evaluator_output?);
+    let result: u8 = GarbledUint::<8>::new(output).into();
+    assert_eq!(result, 2 * 5 + 3 - 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_mismatched_checkpoints() -> Result<()> {
+    let (circuit, input) = multi_arithmetic(2_u8, 5_u8, 3_u8, 4_u8);
+    let (other, _) = multi_arithmetic(2_u16, 5_u16, 3_u16, 4_u16);
+    let (mut transport, _peer) = ChannelTransport::pair();
+
+    let mut garbler = Checkpoint::garbler(&circuit, &input)?;
+    assert!(run_evaluator(&mut transport, &circuit, &mut garbler)
+        .await
+        .is_err());
+    assert!(run_garbler(&mut transport, &other, &mut garbler)
+        .await
+        .is_err());
+
+    let mut data = garbler.to_bytes()?;
+    data[0] = 0xff;
+    assert!(Checkpoint::from_bytes(&data).is_err());
+    Ok(())
+}
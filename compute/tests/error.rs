@@ -0,0 +1,152 @@
+use compute::executor::LocalSimulator;
+use compute::prelude::*;
+use compute::transport::{ErrorKind, RemoteError};
+use std::sync::Arc;
+
+/// Fails every execution with a transport error, like a network executor that lost its peer.
+struct DisconnectedExecutor;
+
+impl Executor for DisconnectedExecutor {
+    fn execute(
+        &self,
+        _circuit: &Arc<Circuit>,
+        _input_contributor: &[bool],
+        _input_evaluator: &[bool],
+    ) -> Result<Vec<bool>, ComputeError> {
+        let error = anyhow::Error::new(RemoteError::new(ErrorKind::Timeout, "no response"));
+        Err(error.context("waiting for the garbler").into())
+    }
+}
+
+#[test]
+fn test_try_operations_match_operators() -> Result<(), ComputeError> {
+    with_executor(Arc::new(PlaintextExecutor), || {
+        let a: GarbledUint8 = 200_u8.into();
+        let b: GarbledUint8 = 7_u8.into();
+
+        assert_eq!(u8::from(a.try_add(&b)?), 200_u8.wrapping_add(7));
+        assert_eq!(u8::from(a.try_sub(&b)?), 193);
+        assert_eq!(u8::from(a.try_mul(&b)?), 200_u8.wrapping_mul(7));
+        assert_eq!(u8::from(a.try_div(&b)?), 28);
+        assert_eq!(u8::from(a.try_rem(&b)?), 4);
+        assert_eq!(u8::from(a.try_xor(&b)?), 200 ^ 7);
+        assert_eq!(u8::from(a.try_not()?), !200);
+        assert_eq!(u8::from(a.try_shr(&GarbledUint::<8>::from(3_u8))?), 25);
+        assert_eq!(a.try_cmp(&b)?, std::cmp::Ordering::Greater);
+        assert!(a.try_eq(&a)?);
+
+        let x: GarbledInt8 = (-5_i8).into();
+        let y: GarbledInt8 = 3_i8.into();
+        assert_eq!(i8::from(x.try_add(&y)?), -2);
+        assert_eq!(i8::from(GarbledInt8::try_mux(&true.into(), &x, &y)?), -5);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_try_operations_reject_operands_of_the_wrong_width() {
+    // the bits are public, so an operand can still end up with the wrong number of them
+    let mut short: GarbledUint8 = 7_u8.into();
+    short.bits.truncate(3);
+    let b: GarbledUint8 = 7_u8.into();
+    assert!(matches!(
+        short.try_add(&b),
+        Err(ComputeError::InputLength {
+            expected: 8,
+            actual: 3
+        })
+    ));
+
+    let mut condition: GarbledBoolean = true.into();
+    condition.bits.push(false);
+    assert!(matches!(
+        GarbledUint8::try_mux(&condition, &b, &b),
+        Err(ComputeError::InputLength {
+            expected: 1,
+            actual: 2
+        })
+    ));
+
+    let empty = GarbledUint::<0>::new(vec![]);
+    assert!(matches!(
+        empty.try_cmp(&empty),
+        Err(ComputeError::UnsupportedWidth(0))
+    ));
+}
+
+#[test]
+fn test_try_operations_return_executor_errors() {
+    let a: GarbledUint8 = 1_u8.into();
+    let result = with_executor(Arc::new(DisconnectedExecutor), || a.try_add(&a));
+
+    let error = result.unwrap_err();
+    assert!(matches!(error, ComputeError::Transport(_)), "{error:?}");
+    assert!(error.to_string().contains("no response"), "{error}");
+}
+
+#[test]
+fn test_encrypted_functions_return_executor_errors() {
+    #[encrypted(execute)]
+    fn addition(a: u8, b: u8) -> u8 {
+        a + b
+    }
+
+    assert_eq!(try_addition(2_u8, 3_u8).unwrap(), 5);
+    let result = with_executor(Arc::new(DisconnectedExecutor), || try_addition(2_u8, 3_u8));
+    assert!(matches!(result, Err(ComputeError::Transport(_))));
+}
+
+#[test]
+fn test_executors_check_input_lengths() {
+    let circuit = Arc::new(Circuit::new(
+        vec![Gate::InContrib, Gate::InEval, Gate::Xor(0, 1)],
+        vec![2],
+    ));
+
+    for executor in [
+        Arc::new(PlaintextExecutor) as SharedExecutor,
+        Arc::new(LocalSimulator::new(f64::INFINITY)),
+    ] {
+        assert!(matches!(
+            executor.execute(&circuit, &[true, false], &[true]),
+            Err(ComputeError::InputLength {
+                expected: 1,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            executor.execute_batch(&circuit, &[vec![true]], &[]),
+            Err(ComputeError::RecordCount {
+                garbler: 1,
+                evaluator: 0
+            })
+        ));
+    }
+}
+
+#[test]
+fn test_compute_errors_survive_anyhow() {
+    let error = anyhow::Error::new(ComputeError::UnsupportedWidth(3));
+    assert!(matches!(
+        ComputeError::from(error),
+        ComputeError::UnsupportedWidth(3)
+    ));
+
+    let error = anyhow::anyhow!("malformed message");
+    assert!(matches!(
+        ComputeError::from(error),
+        ComputeError::Protocol(_)
+    ));
+}
+
+#[test]
+fn test_lsb_of_empty_vector() {
+    let empty = GateIndexVec::default();
+    assert!(matches!(empty.lsb(), Err(ComputeError::CircuitBuild(_))));
+    assert!(matches!(
+        GateIndex::try_from(&empty),
+        Err(ComputeError::CircuitBuild(_))
+    ));
+    assert!(GarbledBoolean::try_from(empty).is_err());
+    assert_eq!(GateIndexVec::new(vec![4, 5]).lsb().unwrap(), 4);
+}
@@ -0,0 +1,218 @@
+use compute::executor::LocalSimulator;
+use compute::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+// Serializes the tests that run encrypted functions on the global executor
+static GLOBAL_EXECUTOR: Mutex<()> = Mutex::new(());
+
+/// Counts the executions it forwards to a simulator without network latency.
+#[derive(Default)]
+struct CountingExecutor {
+    calls: AtomicUsize,
+}
+
+impl Executor for CountingExecutor {
+    fn execute(
+        &self,
+        circuit: &Arc<Circuit>,
+        input_contributor: &[bool],
+        input_evaluator: &[bool],
+    ) -> Result<Vec<bool>, ComputeError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        LocalSimulator::new(f64::INFINITY).execute(circuit, input_contributor, input_evaluator)
+    }
+}
+
+#[encrypted(execute)]
+fn addition(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+#[test]
+fn test_with_executor_is_scoped() {
+    let _guard = GLOBAL_EXECUTOR.lock().unwrap();
+    let executor = Arc::new(CountingExecutor::default());
+
+    let result = with_executor(executor.clone(), || {
+        assert_eq!(addition(2_u8, 3_u8), 5);
+        // other threads keep using the global executor
+        std::thread::spawn(|| addition(1_u8, 1_u8)).join().unwrap()
+    });
+    assert_eq!(result, 2);
+    assert_eq!(executor.calls.load(Ordering::SeqCst), 1);
+
+    assert_eq!(addition(4_u8, 4_u8), 8);
+    assert_eq!(executor.calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_execute_with_explicit_executor() -> anyhow::Result<()> {
+    let executor = CountingExecutor::default();
+
+    let mut builder = WRK17CircuitBuilder::default();
+    let a = builder.input::<8>(&7_u8.into());
+    let b = builder.input::<8>(&9_u8.into());
+    let output = builder.add(&a, &b);
+    let circuit = builder.compile(&output);
+
+    let result: u8 = builder.execute_with::<8>(&circuit, &executor)?.into();
+    assert_eq!(result, 16);
+    assert_eq!(executor.calls.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[test]
+fn test_set_executor_replaces_global() {
+    let _guard = GLOBAL_EXECUTOR.lock().unwrap();
+    let executor = Arc::new(CountingExecutor::default());
+    let previous = set_executor(executor.clone());
+
+    let result = std::thread::spawn(|| addition(20_u8, 22_u8))
+        .join()
+        .unwrap();
+    assert_eq!(result, 42);
+    assert_eq!(executor.calls.load(Ordering::SeqCst), 1);
+
+    set_executor(previous);
+}
+
+#[test]
+fn test_plaintext_executor_matches_protocol() -> anyhow::Result<()> {
+    #[encrypted(compile)]
+    fn mixed(a: u16, b: u16, c: u16) -> u16 {
+        if a + b > c {
+            a * c
+        } else {
+            (c - b) ^ 0xff
+        }
+    }
+
+    let simulator = LocalSimulator::new(f64::INFINITY);
+    for (a, b, c) in [(1_u16, 2, 3), (40, 2, 7), (1000, 24, 65535), (0, 0, 0)] {
+        let (circuit, inputs) = mixed(a, b, c);
+        let expected = simulator.execute(&circuit, &inputs, &[])?;
+        let result = PlaintextExecutor.execute(&circuit, &inputs, &[])?;
+        assert_eq!(result, expected, "inputs {} {} {}", a, b, c);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_plaintext_executor_evaluator_inputs() -> anyhow::Result<()> {
+    let circuit = Arc::new(Circuit::new(
+        vec![
+            Gate::InContrib,
+            Gate::InEval,
+            Gate::Xor(0, 1),
+            Gate::And(0, 1),
+            Gate::Not(3),
+        ],
+        vec![2, 3, 4],
+    ));
+
+    let result = PlaintextExecutor.execute(&circuit, &[true], &[false])?;
+    assert_eq!(result, vec![true, false, true]);
+
+    assert!(PlaintextExecutor.execute(&circuit, &[true], &[]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_local_simulator_records_network_time() -> anyhow::Result<()> {
+    use compute::network::{NetworkProfile, SimulationMode};
+    use std::time::{Duration, Instant};
+
+    let profile = NetworkProfile::intercontinental().rtt(Duration::from_secs(10));
+    let simulator = LocalSimulator::with_profile(profile).mode(SimulationMode::Record);
+
+    let mut builder = WRK17CircuitBuilder::default();
+    let a = builder.input::<16>(&300_u16.into());
+    let b = builder.input::<16>(&700_u16.into());
+    let output = builder.mul(&a, &b);
+    let circuit = Arc::new(builder.compile(&output));
+
+    let started = Instant::now();
+    let (result, estimate) = simulator.simulate(&circuit, builder.inputs(), &[])?;
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let result: u16 = GarbledUint::<16>::new(result).into();
+    assert_eq!(result, 300_u16.wrapping_mul(700));
+
+    // every message takes at least half a round trip
+    assert!(estimate.messages >= 1);
+    assert!(estimate.network_time >= Duration::from_secs(5) * estimate.messages as u32);
+    assert!(estimate.wall_time() >= estimate.network_time);
+    Ok(())
+}
+
+#[test]
+fn test_execute_with_report() -> anyhow::Result<()> {
+    use compute::report::Direction;
+
+    let mut builder = WRK17CircuitBuilder::default();
+    let a = builder.input::<8>(&12_u8.into());
+    let b = builder.input::<8>(&30_u8.into());
+    let output = builder.add(&a, &b);
+    let circuit = Arc::new(builder.compile(&output));
+
+    let simulator = LocalSimulator::new(f64::INFINITY);
+    let (result, report) = simulator.execute_with_report(&circuit, builder.inputs(), &[])?;
+    let result: u8 = GarbledUint::<8>::new(result).into();
+    assert_eq!(result, 42);
+
+    assert_eq!(report.gates.contrib_inputs, 16);
+    assert_eq!(report.gates.and, circuit.and_gates());
+    assert_eq!(report.messages.len() as u32, 2 * report.rounds + 1);
+    assert_eq!(
+        report.total_bytes(),
+        report.bytes_sent(Direction::GarblerToEvaluator)
+            + report.bytes_sent(Direction::EvaluatorToGarbler)
+    );
+    let estimate = report.network.as_ref().unwrap();
+    assert_eq!(estimate.bytes, report.total_bytes());
+    assert_eq!(estimate.compute_time, report.garbling + report.evaluating);
+
+    // executors that don't exchange messages still report the circuit
+    let (_, report) = PlaintextExecutor.execute_with_report(&circuit, builder.inputs(), &[])?;
+    assert_eq!(report.gates.total(), circuit.gates().len());
+    assert!(report.messages.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_execute_batch() -> anyhow::Result<()> {
+    let circuit = Arc::new(Circuit::new(
+        vec![
+            Gate::InContrib,
+            Gate::InEval,
+            Gate::Xor(0, 1),
+            Gate::And(0, 1),
+            Gate::Not(3),
+        ],
+        vec![2, 3, 4],
+    ));
+    let garbler_inputs = vec![vec![false], vec![false], vec![true], vec![true]];
+    let evaluator_inputs = vec![vec![false], vec![true], vec![false], vec![true]];
+    let expected = vec![
+        vec![false, false, true],
+        vec![true, false, true],
+        vec![true, false, true],
+        vec![false, true, false],
+    ];
+
+    let simulator = LocalSimulator::new(f64::INFINITY);
+    let counting = CountingExecutor::default();
+    let executors: [&dyn Executor; 3] = [&simulator, &PlaintextExecutor, &counting];
+    for executor in executors {
+        let outputs = executor.execute_batch(&circuit, &garbler_inputs, &evaluator_inputs)?;
+        assert_eq!(outputs, expected);
+        assert!(executor
+            .execute_batch(&circuit, &garbler_inputs, &evaluator_inputs[1..])
+            .is_err());
+        assert!(executor.execute_batch(&circuit, &[], &[])?.is_empty());
+    }
+    // the default implementation runs one execution per record
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 4);
+    Ok(())
+}
@@ -142,3 +142,15 @@ fn test_from_uint_to_int_i128() {
     let result: i128 = int.into();
     assert_eq!(result, 12297829382473034410_u128 as i128);
 }
+
+#[test]
+fn test_new_checks_width() {
+    assert!(GarbledInt8::try_new(vec![false; 8]).is_ok());
+    assert!(matches!(
+        GarbledInt16::try_new(vec![false; 8]),
+        Err(ComputeError::InputLength {
+            expected: 16,
+            actual: 8
+        })
+    ));
+}
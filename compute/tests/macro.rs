@@ -1310,3 +1310,234 @@ fn test_macro_if() {
     let result = if_test(a);
     assert_eq!(result, 100);
 }
+
+#[test]
+fn test_macro_if_without_else() {
+    #[encrypted(execute)]
+    fn cap(a: u8) -> u8 {
+        let mut result = a;
+        if a > 100 {
+            result = 100;
+        }
+        result
+    }
+
+    assert_eq!(cap(42_u8), 42);
+    assert_eq!(cap(132_u8), 100);
+}
+
+#[test]
+fn test_macro_if_without_else_chain() {
+    #[encrypted(execute)]
+    fn fee(amount: u16, premium: u16) -> u16 {
+        let mut fee = 10;
+        let mut discount = 0;
+        if amount > 1000 {
+            fee = 5;
+        } else if premium == 1 {
+            fee = 7;
+            discount = 2;
+        }
+        fee - discount
+    }
+
+    assert_eq!(fee(2000_u16, 1_u16), 5);
+    assert_eq!(fee(500_u16, 1_u16), 5);
+    assert_eq!(fee(500_u16, 0_u16), 10);
+}
+
+#[test]
+fn test_macro_if_assigns_in_both_branches() {
+    #[encrypted(execute)]
+    fn split(a: u8, b: u8) -> u8 {
+        let mut low = a;
+        let mut high = b;
+        if a > b {
+            low = b;
+            high = a;
+        } else {
+            low = a;
+        }
+        high - low
+    }
+
+    assert_eq!(split(3_u8, 10_u8), 7);
+    assert_eq!(split(10_u8, 3_u8), 7);
+    assert_eq!(split(4_u8, 4_u8), 0);
+}
+
+#[test]
+fn test_macro_if_with_value_and_assignment() {
+    #[encrypted(execute)]
+    fn bump(a: u8) -> u8 {
+        let mut count = a;
+        let doubled = if a < 10 {
+            count = a + 1;
+            count
+        } else {
+            count
+        };
+        doubled + count
+    }
+
+    assert_eq!(bump(3_u8), 8);
+    assert_eq!(bump(20_u8), 40);
+}
+
+#[test]
+fn test_macro_early_return() {
+    #[encrypted(execute)]
+    fn loan_limit(score: u16, income: u16) -> u16 {
+        if score < 500 {
+            return 0;
+        }
+        let mut limit = income * 2;
+        if score > 750 {
+            limit = income * 4;
+        } else if income < 100 {
+            return 50;
+        }
+        limit
+    }
+
+    assert_eq!(loan_limit(400_u16, 1000_u16), 0);
+    assert_eq!(loan_limit(800_u16, 1000_u16), 4000);
+    assert_eq!(loan_limit(600_u16, 1000_u16), 2000);
+    assert_eq!(loan_limit(600_u16, 80_u16), 50);
+}
+
+#[test]
+fn test_macro_return_at_end() {
+    #[encrypted(execute)]
+    fn add(a: u8, b: u8) -> u8 {
+        let sum = a + b;
+        return sum;
+    }
+
+    assert_eq!(add(2_u8, 3_u8), 5);
+}
+
+#[test]
+fn test_macro_early_return_keeps_branch_scope() {
+    #[encrypted(execute)]
+    fn shadow(a: u8) -> u8 {
+        let x = a + 1;
+        if a > 10 {
+            let x = a + 100;
+            if a > 200 {
+                return x;
+            }
+        }
+        x
+    }
+
+    assert_eq!(shadow(5_u8), 6);
+    assert_eq!(shadow(20_u8), 21);
+    assert_eq!(shadow(201_u8), 45);
+
+    #[encrypted(execute)]
+    fn assign_then_shadow(a: u8) -> u8 {
+        let mut x = a;
+        if a > 10 {
+            x = x + 1;
+            let x = x * 2;
+            if a > 50 {
+                return x;
+            }
+        }
+        x
+    }
+
+    assert_eq!(assign_then_shadow(5_u8), 5);
+    assert_eq!(assign_then_shadow(20_u8), 21);
+    assert_eq!(assign_then_shadow(60_u8), 122);
+}
+
+#[test]
+fn test_macro_match_guards() {
+    #[encrypted(execute)]
+    fn tier(a: u8, b: u8) -> u8 {
+        match a {
+            0 => 1,
+            x if x > 200 => 4,
+            10..=20 if b == 1 => 3,
+            _ if b > 5 => 2,
+            _ => 0,
+        }
+    }
+
+    assert_eq!(tier(0_u8, 9_u8), 1);
+    assert_eq!(tier(250_u8, 0_u8), 4);
+    assert_eq!(tier(15_u8, 1_u8), 3);
+    assert_eq!(tier(15_u8, 0_u8), 0);
+    assert_eq!(tier(15_u8, 9_u8), 2);
+    assert_eq!(tier(100_u8, 9_u8), 2);
+    assert_eq!(tier(100_u8, 1_u8), 0);
+}
+
+#[test]
+fn test_macro_artifact() {
+    #[encrypted(artifact)]
+    fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
+        let res = a * b;
+        let res = res + c;
+        res - d + 1
+    }
+
+    let artifact = multi_arithmetic::<u8>();
+    assert!(std::sync::Arc::ptr_eq(&artifact, &multi_arithmetic::<u8>()));
+    assert_eq!(artifact.signature().name, "multi_arithmetic");
+    assert_eq!(
+        artifact.layout().width(Party::Garbler),
+        artifact.circuit().contrib_inputs()
+    );
+
+    let inputs: Vec<GarbledUint<8>> = [2_u8, 5, 3, 4].iter().map(|&x| x.into()).collect();
+    let arguments: Vec<&[bool]> = inputs.iter().map(|x| x.bits.as_slice()).collect();
+    let input = artifact.layout().bind(Party::Garbler, &arguments).unwrap();
+
+    let result = get_executor()
+        .execute(artifact.circuit(), &input, &[])
+        .unwrap();
+    let result: u8 = GarbledUint::<8>::new(result).into();
+    assert_eq!(result, 2 * 5 + 3 - 4 + 1);
+
+    // each bit width gets its own circuit
+    let wide = multi_arithmetic::<u16>();
+    assert_eq!(wide.layout().parameter("d").unwrap().width, 16);
+    assert_eq!(artifact.signature().output, "u8");
+    assert_eq!(wide.signature().output, "u16");
+}
+
+#[test]
+fn test_macro_artifact_has_only_used_inputs() {
+    #[encrypted(artifact)]
+    fn addition(a: u8, b: u8) -> u8 {
+        a + b
+    }
+
+    // without literals in the body, the parameters are the only garbler inputs
+    let artifact = addition::<u8>();
+    assert_eq!(artifact.layout().width(Party::Garbler), 16);
+    assert_eq!(artifact.circuit().contrib_inputs(), 16);
+}
+
+#[test]
+fn test_macro_compile_reuses_circuit() {
+    #[encrypted(compile)]
+    fn addition(a: u8, b: u8) -> u8 {
+        a + b + 1
+    }
+
+    let (first, first_inputs) = addition(2_u8, 5_u8);
+    let (second, second_inputs) = addition(7_u8, 9_u8);
+    // the cached circuit is shared, not copied
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+    assert_ne!(first_inputs, second_inputs);
+
+    let result = get_executor()
+        .execute(&second, &second_inputs, &[])
+        .unwrap();
+    let result: u8 = GarbledUint::<8>::new(result).into();
+    assert_eq!(result, 17);
+}
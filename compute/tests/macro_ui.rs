@@ -0,0 +1,6 @@
+/// Checks the compile errors `#[encrypted]` reports for constructs it can't turn into a circuit.
+#[test]
+fn test_macro_diagnostics() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use compute::batch;
+use compute::prelude::*;
+use compute::protocol::{run_evaluator, run_garbler};
+use compute::transport::ChannelTransport;
+
+#[encrypted(compile)]
+fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
+    let res = a * b;
+    let res = res + c;
+    res - d
+}
+
+#[tokio::test]
+async fn test_garbler_and_evaluator_drivers() -> Result<()> {
+    let (circuit, input) = multi_arithmetic(2_u8, 5_u8, 3_u8, 4_u8);
+    let (mut garbler_side, mut evaluator_side) = ChannelTransport::pair();
+
+    let evaluator_circuit = circuit.clone();
+    let evaluator =
+        tokio::spawn(
+            async move { run_evaluator(&mut evaluator_side, &evaluator_circuit, &[]).await },
+        );
+    let garbler_output = run_garbler(&mut garbler_side, &circuit, &input).await?;
+    let evaluator_output = evaluator.await??;
+
+    assert_eq!(garbler_output, evaluator_output);
+    let result: u8 = GarbledUint::<8>::new(garbler_output).into();
+    assert_eq!(result, 2 * 5 + 3 - 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sessions_run_concurrently() -> Result<()> {
+    let mut sessions = Vec::new();
+    for i in 0..8_u8 {
+        let (circuit, input) = multi_arithmetic(i, 3_u8, 1_u8, 0_u8);
+        let (mut garbler_side, mut evaluator_side) = ChannelTransport::pair();
+        let evaluator_circuit = circuit.clone();
+        tokio::spawn(
+            async move { run_evaluator(&mut evaluator_side, &evaluator_circuit, &[]).await },
+        );
+        sessions.push(tokio::spawn(async move {
+            run_garbler(&mut garbler_side, &circuit, &input).await
+        }));
+    }
+
+    for (i, session) in sessions.into_iter().enumerate() {
+        let result: u8 = GarbledUint::<8>::new(session.await??).into();
+        assert_eq!(result, i as u8 * 3 + 1);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_garbler_fails_when_evaluator_disconnects() {
+    let (circuit, input) = multi_arithmetic(1_u8, 1_u8, 1_u8, 1_u8);
+    let (mut garbler_side, evaluator_side) = ChannelTransport::pair();
+    drop(evaluator_side);
+
+    assert!(run_garbler(&mut garbler_side, &circuit, &input)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_batch_drivers() -> Result<()> {
+    let (circuit, _) = multi_arithmetic(0_u8, 0_u8, 0_u8, 0_u8);
+    let inputs: Vec<Vec<bool>> = (0..16_u8)
+        .map(|i| multi_arithmetic(i, 3_u8, 1_u8, 0_u8).1)
+        .collect();
+    let (mut garbler_side, mut evaluator_side) = ChannelTransport::pair();
+
+    let evaluator_circuit = circuit.clone();
+    let evaluator = tokio::spawn(async move {
+        let inputs = vec![vec![]; 16];
+        batch::run_evaluator(&mut evaluator_side, &evaluator_circuit, &inputs).await
+    });
+    let outputs = batch::run_garbler(&mut garbler_side, &circuit, &inputs).await?;
+    assert_eq!(outputs, evaluator.await??);
+
+    assert_eq!(outputs.len(), 16);
+    for (i, output) in outputs.into_iter().enumerate() {
+        let result: u8 = GarbledUint::<8>::new(output).into();
+        assert_eq!(result, i as u8 * 3 + 1);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_drivers_reject_mismatched_records() {
+    let (circuit, input) = multi_arithmetic(1_u8, 1_u8, 1_u8, 1_u8);
+    let (mut garbler_side, evaluator_side) = ChannelTransport::pair();
+
+    // the evaluator expects one record, and drops the connection when it gets two
+    let inputs = [input.clone(), input];
+    let evaluator = async {
+        let (mut evaluator_side, inputs) = (evaluator_side, [vec![]]);
+        batch::run_evaluator(&mut evaluator_side, &circuit, &inputs).await
+    };
+    let (garbler, evaluator) = tokio::join!(
+        batch::run_garbler(&mut garbler_side, &circuit, &inputs),
+        evaluator,
+    );
+    assert!(garbler.is_err());
+    assert!(evaluator.is_err());
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "deterministic")]
+
+use anyhow::Result;
+use compute::executor::LocalSimulator;
+use compute::prelude::*;
+use compute::report::Direction;
+use compute::transcript::Transcript;
+use std::time::Duration;
+
+#[encrypted(compile)]
+fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
+    let res = a * b;
+    let res = res + c;
+    res - d
+}
+
+fn simulator() -> LocalSimulator {
+    LocalSimulator::new(f64::INFINITY).seeded([1; 32], [2; 32])
+}
+
+#[test]
+fn test_seeded_parties_are_deterministic() -> Result<()> {
+    let (circuit, input) = multi_arithmetic(2_u8, 5_u8, 3_u8, 4_u8);
+    let (mut first, mut to_evaluator) = GatewayGarbler::start_seeded(&circuit, &input, [1; 32])?;
+    let (mut second, message) = GatewayGarbler::start_seeded(&circuit, &input, [1; 32])?;
+    assert_eq!(to_evaluator, message);
+
+    let mut evaluator = GatewayEvaluator::new_seeded(&circuit, &[], [2; 32])?;
+    while !first.is_complete() {
+        let (next_evaluator, to_garbler) = evaluator.next(&to_evaluator)?;
+        evaluator = next_evaluator;
+        (first, to_evaluator) = first.next(&to_garbler)?;
+        let message;
+        (second, message) = second.next(&to_garbler)?;
+        assert_eq!(to_evaluator, message);
+    }
+    let result: u8 = GarbledUint::<8>::new(evaluator.output(&to_evaluator)?).into();
+    assert_eq!(result, 2 * 5 + 3 - 4);
+    Ok(())
+}
+
+#[test]
+fn test_replays_recorded_transcript() -> Result<()> {
+    let (circuit, input) = multi_arithmetic(2_u8, 5_u8, 3_u8, 4_u8);
+    let (output, transcript) = simulator().record(&circuit, &input, &[])?;
+    assert_eq!(transcript.messages[0].step, 0);
+    assert_eq!(
+        transcript.messages[0].direction,
+        Direction::GarblerToEvaluator
+    );
+
+    let transcript = Transcript::from_bytes(&transcript.to_bytes()?)?;
+    assert_eq!(
+        simulator().replay(&circuit, &input, &[], &transcript)?,
+        output
+    );
+    Ok(())
+}
+
+#[test]
+fn test_replay_reports_divergence() -> Result<()> {
+    let (circuit, input) = multi_arithmetic(2_u8, 5_u8, 3_u8, 4_u8);
+    let (_, transcript) = simulator().record(&circuit, &input, &[])?;
+
+    let mut tampered = transcript.clone();
+    tampered.messages[0].data[0] ^= 1;
+    let error = simulator()
+        .replay(&circuit, &input, &[], &tampered)
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("diverged at step 0"),
+        "{error:#}"
+    );
+
+    let mut truncated = transcript.clone();
+    truncated.messages.pop();
+    assert!(simulator()
+        .replay(&circuit, &input, &[], &truncated)
+        .is_err());
+
+    let mut extended = transcript;
+    extended.push(99, Direction::EvaluatorToGarbler, Duration::ZERO, &[]);
+    assert!(simulator()
+        .replay(&circuit, &input, &[], &extended)
+        .is_err());
+    Ok(())
+}
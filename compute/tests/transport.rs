@@ -0,0 +1,257 @@
+use anyhow::Result;
+use compute::prelude::*;
+use compute::protocol::{decode_output, run_evaluator, run_garbler};
+use compute::report::Direction;
+use compute::transcript::{self, Transcript};
+use compute::transport::{
+    ChannelTransport, QuicTransport, TcpTransport, TranscriptRecorder, Transport,
+};
+use std::sync::Arc;
+
+#[encrypted(compile)]
+fn addition(a: u16, b: u16) -> u16 {
+    a + b
+}
+
+/// Runs the protocol over the given pair of transports and checks the result on both sides.
+async fn run_addition<G, E>(mut garbler: G, mut evaluator: E) -> Result<()>
+where
+    G: Transport,
+    E: Transport + 'static,
+{
+    let (circuit, input) = addition(1200_u16, 34_u16);
+
+    let evaluator_circuit = circuit.clone();
+    let evaluator = tokio::spawn(async move {
+        let output = run_evaluator(&mut evaluator, &evaluator_circuit, &[]).await;
+        // keep the connection open until the garbler has read the output
+        let _ = evaluator.receive().await;
+        output
+    });
+
+    let output = run_garbler(&mut garbler, &circuit, &input).await?;
+    drop(garbler);
+    assert_eq!(evaluator.await??, output);
+
+    let result: u16 = GarbledUint::<16>::new(output).into();
+    assert_eq!(result, 1234);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tcp_transport() -> Result<()> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let (garbler, accepted) = tokio::join!(TcpTransport::connect(addr), listener.accept());
+    let evaluator = TcpTransport::from_tcp(accepted?.0);
+    run_addition(garbler?, evaluator).await
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_transport() -> Result<()> {
+    use compute::transport::UnixTransport;
+
+    let path = std::env::temp_dir().join(format!("compute-transport-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path)?;
+
+    let (garbler, accepted) = tokio::join!(UnixTransport::connect(&path), listener.accept());
+    let evaluator = UnixTransport::from_unix(accepted?.0);
+    let result = run_addition(garbler?, evaluator).await;
+    std::fs::remove_file(&path)?;
+    result
+}
+
+/// Connects a quinn client to a quinn server with a self-signed certificate over loopback.
+async fn quic_connections() -> Result<(quinn::Connection, quinn::Connection)> {
+    use quinn::rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+    use quinn::rustls::RootCertStore;
+    use quinn::{ClientConfig, Endpoint, ServerConfig};
+
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+    let cert = certified.cert.der().clone();
+    let key = PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der());
+
+    let server_config =
+        ServerConfig::with_single_cert(vec![cert.clone()], PrivateKeyDer::from(key))?;
+    let server = Endpoint::server(server_config, "127.0.0.1:0".parse()?)?;
+
+    let mut roots = RootCertStore::empty();
+    roots.add(cert)?;
+    let mut client = Endpoint::client("127.0.0.1:0".parse()?)?;
+    client.set_default_client_config(ClientConfig::with_root_certificates(Arc::new(roots))?);
+
+    let connecting = client.connect(server.local_addr()?, "localhost")?;
+    let incoming = server.accept().await.expect("server endpoint closed");
+    let (client_connection, server_connection) = tokio::join!(connecting, incoming);
+    Ok((client_connection?, server_connection?))
+}
+
+#[tokio::test]
+async fn test_quic_transport() -> Result<()> {
+    let (client_connection, server_connection) = quic_connections().await?;
+    let (circuit, input) = addition(1200_u16, 34_u16);
+
+    // the stream only shows up on the server once the garbler sends its first message
+    let mut garbler = QuicTransport::open(&client_connection).await?;
+    let garbler_circuit = circuit.clone();
+    let garbler =
+        tokio::spawn(async move { run_garbler(&mut garbler, &garbler_circuit, &input).await });
+
+    let mut evaluator = QuicTransport::accept(&server_connection).await?;
+    let evaluator_output = run_evaluator(&mut evaluator, &circuit, &[]).await?;
+    let garbler_output = garbler.await??;
+    assert_eq!(garbler_output, evaluator_output);
+
+    let result: u16 = GarbledUint::<16>::new(garbler_output).into();
+    assert_eq!(result, 1234);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_transport_enforces_max_frame_size() -> Result<()> {
+    use compute::transport::frame::FrameError;
+    use compute::transport::StreamTransport;
+
+    let (a, b) = tokio::io::duplex(1024);
+    let mut sender = StreamTransport::from_stream(a);
+    let mut receiver = StreamTransport::from_stream(b).with_max_frame_size(16);
+
+    sender.send(vec![1; 16]).await?;
+    assert_eq!(receiver.receive().await?, vec![1; 16]);
+
+    sender.send(vec![1; 17]).await?;
+    let error = receiver.receive().await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<FrameError>(),
+        Some(FrameError::TooLarge { size: 17, max: 16 })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_abort_reports_remote_error() -> Result<()> {
+    use compute::transport::{ChannelTransport, ErrorKind, RemoteError, StreamTransport};
+
+    let (a, b) = tokio::io::duplex(1024);
+    let mut stream_pair = (
+        StreamTransport::from_stream(a),
+        StreamTransport::from_stream(b),
+    );
+    let mut channel_pair = ChannelTransport::pair();
+    let pairs: [(&mut dyn Transport, &mut dyn Transport); 2] = [
+        (&mut stream_pair.0, &mut stream_pair.1),
+        (&mut channel_pair.0, &mut channel_pair.1),
+    ];
+
+    for (sender, receiver) in pairs {
+        let error = RemoteError::new(ErrorKind::Busy, "too many sessions");
+        sender.abort(error.clone()).await?;
+        let received = receiver.receive().await.unwrap_err();
+        assert_eq!(received.downcast_ref::<RemoteError>(), Some(&error));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_chunked_stream_transport() -> Result<()> {
+    use compute::transport::StreamTransport;
+
+    // the duplex buffer is smaller than a message, so the sender waits for the receiver
+    let (a, b) = tokio::io::duplex(64);
+    let garbler = StreamTransport::from_stream(a).with_chunk_size(16);
+    let evaluator = StreamTransport::from_stream(b)
+        .with_max_frame_size(16)
+        .with_max_message_size(1 << 20)
+        .with_chunk_size(16);
+    run_addition(garbler, evaluator).await
+}
+
+#[tokio::test]
+async fn test_stream_transport_enforces_max_message_size() -> Result<()> {
+    use compute::transport::frame::FrameError;
+    use compute::transport::StreamTransport;
+
+    let (a, b) = tokio::io::duplex(1024);
+    let mut sender = StreamTransport::from_stream(a).with_chunk_size(4);
+    let mut receiver = StreamTransport::from_stream(b)
+        .with_max_frame_size(4)
+        .with_max_message_size(16);
+
+    sender.send(vec![1; 16]).await?;
+    assert_eq!(receiver.receive().await?, vec![1; 16]);
+
+    sender.send(vec![1; 17]).await?;
+    let error = receiver.receive().await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<FrameError>(),
+        Some(FrameError::MessageTooLarge { size: 17, max: 16 })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transcript_recorder() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("compute-{}.transcript", std::process::id()));
+    let (garbler, evaluator) = ChannelTransport::pair();
+    let garbler = TranscriptRecorder::create(garbler, Party::Garbler, &path).await?;
+    run_addition(garbler, evaluator).await?;
+    let data = std::fs::read(&path)?;
+    #[cfg(unix)]
+    let mode = std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&path)?.permissions());
+    std::fs::remove_file(&path)?;
+    // transcripts hold garbled tables and OT messages, so only the owner may read them
+    #[cfg(unix)]
+    assert_eq!(mode & 0o777, 0o600);
+
+    // the garbler's first message, a reply and a message per step, and the output
+    let transcript = Transcript::from_bytes(&data)?;
+    let steps = transcript.sent(Direction::GarblerToEvaluator).count() as u32 - 1;
+    assert_eq!(transcript.messages.len() as u32, 2 * steps + 2);
+    for (i, message) in transcript.messages.iter().enumerate() {
+        let direction = match i % 2 {
+            0 => Direction::GarblerToEvaluator,
+            _ => Direction::EvaluatorToGarbler,
+        };
+        assert_eq!(message.direction, direction);
+        assert_eq!(message.step as usize, i.div_ceil(2));
+    }
+    let output = decode_output(&transcript.messages.last().unwrap().data)?;
+    assert_eq!(u16::from(GarbledUint::<16>::new(output)), 1234);
+
+    // a transcript cut off while writing the last message is read without it
+    let truncated = Transcript::from_bytes(&data[..data.len() - 1])?;
+    assert_eq!(truncated.messages.len(), transcript.messages.len() - 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transcript_recorder_skips_failed_sends() -> Result<()> {
+    let path =
+        std::env::temp_dir().join(format!("compute-{}-failed.transcript", std::process::id()));
+    let (garbler, evaluator) = ChannelTransport::pair();
+    drop(evaluator);
+    let mut garbler = TranscriptRecorder::create(garbler, Party::Garbler, &path).await?;
+    assert!(garbler.send(vec![1, 2, 3]).await.is_err());
+
+    let transcript = Transcript::load(&path)?;
+    std::fs::remove_file(&path)?;
+    assert!(transcript.messages.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_replay_reports_incomplete_transcripts() -> Result<()> {
+    let (circuit, input) = addition(1200_u16, 34_u16);
+    let mut transcript = Transcript::new();
+    let error = transcript::replay_evaluator(&circuit, &[], &transcript).unwrap_err();
+    assert!(error.to_string().contains("transcript ends"), "{error:#}");
+
+    let (_, first) = GatewayGarbler::start(&circuit, &input)?;
+    transcript.push(0, Direction::GarblerToEvaluator, Default::default(), &first);
+    assert!(transcript::replay_evaluator(&circuit, &[], &transcript).is_err());
+    assert!(transcript::replay_garbler(&circuit, &input, &transcript).is_err());
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use compute::prelude::*;
+
+#[encrypted(execute)]
+fn classify(a: u8) -> u8 {
+    match a {}
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use compute::prelude::*;
+
+#[encrypted(execute)]
+fn classify(a: u8) -> u8 {
+    if let 10.. = a {
+        1
+    } else {
+        0
+    }
+}
+
+fn main() {}
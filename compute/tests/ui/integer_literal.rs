@@ -0,0 +1,8 @@
+use compute::prelude::*;
+
+#[encrypted(execute)]
+fn add(a: u128) -> u128 {
+    a + 1_000_000_000_000_000_000_000_000_000_000_000_000_000
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use compute::prelude::*;
+
+#[encrypted(execute)]
+fn classify(a: u8, b: u8) -> u8 {
+    match a {
+        0 => 1,
+        _ if b > 5 => 2,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use compute::prelude::*;
+
+#[encrypted(execute)]
+fn classify(a: u8) -> u8 {
+    match a {
+        1 | 2 => 10,
+        _ => 0,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use compute::prelude::*;
+
+#[encrypted(execute)]
+fn answer() -> u8 {
+    42
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use compute::prelude::*;
+
+#[encrypted(execute)]
+fn add(a: u8, b: u8) {
+    a + b;
+}
+
+fn main() {}
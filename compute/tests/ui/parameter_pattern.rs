@@ -0,0 +1,8 @@
+use compute::prelude::*;
+
+#[encrypted(execute)]
+fn add((a, b): (u8, u8)) -> u8 {
+    a + b
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use compute::prelude::*;
+
+#[encrypted(execute)]
+fn classify(a: u8) -> u8 {
+    match a {
+        0 => return 1,
+        _ => 2,
+    }
+}
+
+fn main() {}
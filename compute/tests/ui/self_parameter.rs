@@ -0,0 +1,12 @@
+use compute::prelude::*;
+
+struct Policy;
+
+impl Policy {
+    #[encrypted(execute)]
+    fn check(&self, a: u8) -> u8 {
+        a
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use compute::prelude::*;
+
+#[encrypted(run)]
+fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+fn main() {}
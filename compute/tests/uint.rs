@@ -1,4 +1,8 @@
-use compute::uint::{GarbledUint128, GarbledUint16, GarbledUint32, GarbledUint64, GarbledUint8};
+use compute::prelude::ComputeError;
+use compute::uint::{
+    GarbledBoolean, GarbledUint, GarbledUint128, GarbledUint16, GarbledUint32, GarbledUint64,
+    GarbledUint8,
+};
 
 #[test]
 fn test_display() {
@@ -52,3 +56,34 @@ fn test_from_u128() {
     let value: u128 = a.into();
     assert_eq!(value, 12297829382473034410);
 }
+
+#[test]
+fn test_new_checks_width() {
+    assert_eq!(
+        GarbledUint8::try_new(vec![true; 8]).unwrap().bits,
+        vec![true; 8]
+    );
+    assert!(matches!(
+        GarbledUint8::try_new(vec![true; 7]),
+        Err(ComputeError::InputLength {
+            expected: 8,
+            actual: 7
+        })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "The number of bits must be 8")]
+fn test_new_panics_on_wrong_width() {
+    GarbledUint8::new(vec![true; 9]);
+}
+
+#[test]
+fn test_from_bool() {
+    let a: GarbledUint<4> = true.into();
+    assert_eq!(a.bits, vec![true, false, false, false]);
+    assert_eq!(u8::from(a), 1);
+
+    let b: GarbledBoolean = false.into();
+    assert!(!bool::from(b));
+}
@@ -0,0 +1,69 @@
+use anyhow::{ensure, Context};
+use clap::Parser;
+use server::tls::{self, CertificateAuthority};
+use std::path::PathBuf;
+
+/// Generates a CA and the certificates of the server and its clients.
+///
+/// Writes `ca.pem`/`ca-key.pem`, the server's `cert.pem`/`key.pem` and
+/// `client-<NAME>.pem`/`client-<NAME>-key.pem` for every client into the output directory.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Directory to write the certificates to
+    #[arg(long, default_value = "server/certs")]
+    out: PathBuf,
+    /// Reuse the CA in the output directory instead of creating a new one
+    #[arg(long)]
+    reuse_ca: bool,
+    /// DNS name or IP address of the server; may be repeated
+    #[arg(long = "server-name", default_value = "localhost")]
+    server_names: Vec<String>,
+    /// Skip the server certificate, e.g. when only adding clients
+    #[arg(long)]
+    no_server: bool,
+    /// Identity of a client to issue a certificate for; may be repeated
+    #[arg(long = "client", value_name = "NAME")]
+    clients: Vec<String>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    for client in &args.clients {
+        // the name becomes part of a file name in `--out`
+        ensure!(
+            !client.is_empty() && !client.contains(['/', '\\']) && !client.contains(".."),
+            "invalid client name {client:?}: it must not contain path separators or `..`"
+        );
+    }
+    std::fs::create_dir_all(&args.out)
+        .with_context(|| format!("creating {}", args.out.display()))?;
+    let path = |name: &str| args.out.join(name);
+
+    let ca = if args.reuse_ca {
+        let stored = tls::load(path("ca.pem"), path("ca-key.pem"))?;
+        CertificateAuthority::from_pem(&stored.cert_pem, &stored.key_pem)?
+    } else {
+        let ca = CertificateAuthority::generate("circuit-sdk CA")?;
+        ca.certificate().write(path("ca.pem"), path("ca-key.pem"))?;
+        println!("CA: {}", path("ca.pem").display());
+        ca
+    };
+
+    if !args.no_server {
+        ca.issue_server(&args.server_names)?
+            .write(path("cert.pem"), path("key.pem"))?;
+        println!(
+            "Server ({}): {}",
+            args.server_names.join(", "),
+            path("cert.pem").display()
+        );
+    }
+
+    for client in &args.clients {
+        let cert = path(&format!("client-{client}.pem"));
+        ca.issue_client(client)?
+            .write(&cert, path(&format!("client-{client}-key.pem")))?;
+        println!("Client {}: {}", client, cert.display());
+    }
+    Ok(())
+}
@@ -1,117 +1,171 @@
 // src/bin/client.rs
+use anyhow::Context;
+use clap::Parser;
 use compute::prelude::*;
-use s2n_quic::{client::Connect, Client};
-use server::util::extract;
-use server::util::prepare;
-use std::{error::Error, net::SocketAddr, path::Path};
-use tracing::debug;
+use compute::protocol::run_garbler;
+use compute::transport::{QuicTransport, Transport};
+use s2n_quic::{client::Connect, Client, Connection};
+use server::config::{self, ClientConfig};
+use server::handshake;
+use server::s2n::S2nTransport;
+use server::tls;
+use server::value::{bind_inputs, decode_output};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{error::Error, net::SocketAddr};
 use tracing::info;
 
-#[encrypted(compile)]
+#[encrypted(artifact)]
 fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
     let res = a * b;
     let res = res + c;
     res - d
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    // Initialize tracing subscriber for logging
-    tracing_subscriber::fmt::init();
+/// Functions built into the binary, run by name.
+fn builtin(name: &str) -> Option<Arc<CircuitArtifact>> {
+    match name {
+        "multi_arithmetic" => Some(multi_arithmetic::<u8>()),
+        _ => None,
+    }
+}
+
+/// Runs an encrypted function on a server, providing the inputs as the garbler.
+#[derive(Parser, Debug)]
+struct Args {
+    /// TOML config file; flags override its settings
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Address of the server
+    #[arg(long)]
+    server: Option<SocketAddr>,
+    /// Name the server's certificate is issued for
+    #[arg(long)]
+    server_name: Option<String>,
+    /// Certificate used to authenticate the server, or the CA that issued it (PEM)
+    #[arg(long)]
+    cert: Option<PathBuf>,
+    /// Client certificate for servers that require mutual TLS (PEM)
+    #[arg(long, requires = "identity_key")]
+    identity_cert: Option<PathBuf>,
+    /// Private key of the client certificate (PEM)
+    #[arg(long, requires = "identity_cert")]
+    identity_key: Option<PathBuf>,
+    /// Function to run
+    #[arg(long)]
+    function: Option<String>,
+    /// Circuit artifact file of the function, if it isn't built into the binary
+    #[arg(long)]
+    artifact: Option<PathBuf>,
+    /// Value of a parameter; may be repeated
+    #[arg(long = "input", value_name = "NAME=VALUE", value_parser = config::parse_input)]
+    inputs: Vec<(String, server::value::InputValue)>,
+    /// Log level (error, warn, info, debug or trace)
+    #[arg(long)]
+    log_level: Option<String>,
+}
 
+impl Args {
+    fn into_config(self) -> anyhow::Result<ClientConfig> {
+        let mut config: ClientConfig = match &self.config {
+            Some(path) => config::load(path)?,
+            None => ClientConfig::default(),
+        };
+        config.server = self.server.unwrap_or(config.server);
+        config.server_name = self.server_name.unwrap_or(config.server_name);
+        config.cert = self.cert.unwrap_or(config.cert);
+        config.identity_cert = self.identity_cert.or(config.identity_cert);
+        config.identity_key = self.identity_key.or(config.identity_key);
+        config.log_level = self.log_level.unwrap_or(config.log_level);
+        if let Some(function) = self.function {
+            config.function = function;
+            config.artifact = None;
+        }
+        config.artifact = self.artifact.or(config.artifact);
+        config.inputs.extend(self.inputs);
+        Ok(config)
+    }
+}
+
+/// Opens a session on an s2n-quic connection, authenticating only the server. The connection
+/// is closed when it is dropped, so it has to be kept until the session is over.
+async fn connect(config: &ClientConfig) -> Result<(Connection, S2nTransport), Box<dyn Error>> {
     let client = Client::builder()
-        .with_tls(Path::new("server/certs/cert.pem"))?
+        .with_tls(config.cert.as_path())?
         .with_io("0.0.0.0:0")?
         .start()?;
 
-    let addr: SocketAddr = "127.0.0.1:4433".parse()?;
-    let connect = Connect::new(addr).with_server_name("localhost");
+    let connect = Connect::new(config.server).with_server_name(config.server_name.clone());
     let mut connection = client.connect(connect).await?;
 
     // Ensure the connection doesn't time out with inactivity
     connection.keep_alive(true)?;
 
-    // Open a new stream and split the receiving and sending sides
-    let mut stream = connection.open_bidirectional_stream().await?;
-    //let (mut receive_stream, mut send_stream) = stream.split();
-
-    // Initialize garbler with sample data
-    let a = 2_u8;
-    let b = 5_u8;
-    let c = 3_u8;
-    let d = 4_u8;
-    //let circuit = Circuit::default();
-    let (circuit, input_garbler) = multi_arithmetic(a, b, c, d);
-    info!("Circuit: {:?}", hex::encode(circuit.blake3_hash()));
-
-    let (mut garbler, mut msg_for_evaluator) = GatewayGarbler::start(&circuit, &input_garbler)?;
+    // Open a new stream for the session
+    let stream = connection.open_bidirectional_stream().await?;
+    Ok((connection, server::s2n::transport(stream)))
+}
 
-    // Send initial message from garbler to evaluator
-    stream
-        .send(prepare(msg_for_evaluator.clone()).into())
+/// Opens a session on a quinn connection, authenticating with the client certificate `cert`.
+async fn connect_mutual_tls(
+    config: &ClientConfig,
+    cert: &Path,
+    key: &Path,
+) -> anyhow::Result<QuicTransport> {
+    let ca = std::fs::read_to_string(&config.cert)
+        .with_context(|| format!("reading {}", config.cert.display()))?;
+    let identity = tls::load(cert, key)?;
+
+    let mut endpoint = quinn::Endpoint::client(([0, 0, 0, 0], 0).into())?;
+    endpoint.set_default_client_config(tls::client_config(&ca, Some(&identity))?);
+    let connection = endpoint
+        .connect(config.server, &config.server_name)?
         .await?;
-    //stream.flush().await?;
-
-    info!(
-        "Message for evaluator, length: {:?}",
-        msg_for_evaluator.len()
-    );
-
-    while let Some(first_chunk) = stream.receive().await? {
-        let (size, mut data) = extract(&first_chunk)?;
-        info!("size from header: {:?}", size);
+    QuicTransport::open(&connection).await
+}
 
-        // Continue receiving until the full message is obtained
-        while data.len() < size as usize {
-            let chunk = stream.receive().await?.expect("no data received");
-            data.extend_from_slice(&chunk);
-        }
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let config = Args::parse().into_config()?;
 
-        info!("Received message, length: {:?}", data.len());
-        debug!("Received data: {:?}", hex::encode(&data));
-
-        if garbler.is_complete() {
-            // Receive the final output from the evaluator
-            //let final_output = stream.receive().await?.expect("no data received");
-            info!("Final output received: {:?}", hex::encode(&data));
-            fn bits_to_u128(mut bits: Vec<u8>) -> u128 {
-                bits.reverse();
-                // Ensure we use at most 128 bits (truncate if necessary)
-                let bit_length = bits.len().min(128);
-                let mut result: u128 = 0;
-
-                // Iterate over the bits, shifting and accumulating them into the result
-                for &bit in &bits[..bit_length] {
-                    result = (result << 1) | u128::from(bit);
-                }
-
-                result
+    // Initialize tracing subscriber for logging
+    let level: tracing::Level = config
+        .log_level
+        .parse()
+        .with_context(|| format!("invalid log level `{}`", config.log_level))?;
+    tracing_subscriber::fmt().with_max_level(level).init();
+
+    let artifact = match &config.artifact {
+        Some(path) => Arc::new(CircuitArtifact::load(path)?),
+        None => builtin(&config.function).with_context(|| {
+            format!(
+                "`{}` is not a built-in function, give the path of its artifact",
+                config.function
+            )
+        })?,
+    };
+    info!("Circuit: {:?}", hex::encode(artifact.blake3_hash()));
+    let input_garbler = bind_inputs(&artifact, &config.inputs)?;
+
+    let (_connection, mut transport): (Option<Connection>, Box<dyn Transport>) =
+        match (&config.identity_cert, &config.identity_key) {
+            (Some(cert), Some(key)) => (
+                None,
+                Box::new(connect_mutual_tls(&config, cert, key).await?),
+            ),
+            (None, None) => {
+                let (connection, transport) = connect(&config).await?;
+                (Some(connection), Box::new(transport))
             }
+            _ => return Err("`identity_cert` and `identity_key` must be set together".into()),
+        };
 
-            println!("The resulting number: {}", bits_to_u128(data));
-            break;
-        }
-
-        // Pass evaluator response to garbler "next" function
-        let (next_garbler, next_message) = garbler.next(&data)?;
-        info!("Steps remaining: {}", next_garbler.steps());
-        garbler = next_garbler;
-
-        info!("Sending message, length: {:?}", next_message.len());
-        // Send the next message back to evaluator
-        stream.send(prepare(next_message.clone()).into()).await?;
-        //stream.flush().await?;
-
-        debug!(
-            "Sent message for garbler: {:?}",
-            hex::encode(msg_for_evaluator.clone())
-        );
-
-        msg_for_evaluator = next_message;
-    }
+    // Make sure the server evaluates the same circuit before starting the protocol
+    handshake::announce(&mut transport, &config.function, artifact.blake3_hash()).await?;
 
-    debug!("last message: {:?}", msg_for_evaluator);
-    println!("Garbler has completed the interaction.");
+    let output = run_garbler(&mut transport, artifact.circuit(), &input_garbler).await?;
+    let result = decode_output(&artifact, &output)?;
+    println!("{}: {}", config.function, result);
 
     Ok(())
 }
@@ -0,0 +1,101 @@
+use anyhow::{bail, Context};
+use clap::{Parser, ValueEnum};
+use compute::prelude::*;
+use compute::transcript::{self, Transcript};
+use server::config;
+use server::value::{bind_party_inputs, decode_output, InputValue};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[encrypted(artifact)]
+fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
+    let res = a * b;
+    let res = res + c;
+    res - d
+}
+
+/// Functions built into the binary, replayed by name.
+fn builtin(name: &str) -> Option<Arc<CircuitArtifact>> {
+    match name {
+        "multi_arithmetic" => Some(multi_arithmetic::<u8>()),
+        _ => None,
+    }
+}
+
+/// The party that handles the recorded messages of the other one.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Replay {
+    /// Feed the recorded garbler messages to a new evaluator
+    Evaluator,
+    /// Feed the recorded evaluator messages to a new garbler
+    Garbler,
+}
+
+/// Prints a transcript recorded by the server and replays it to reproduce failures offline.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Transcript file
+    transcript: PathBuf,
+    /// Function the transcript was recorded for
+    #[arg(long, default_value = "multi_arithmetic")]
+    function: String,
+    /// Circuit artifact file of the function, if it isn't built into the binary
+    #[arg(long)]
+    artifact: Option<PathBuf>,
+    /// Party to replay the transcript against
+    #[arg(long = "as", value_enum, default_value = "evaluator")]
+    party: Replay,
+    /// Value of a parameter of the replayed party; may be repeated
+    #[arg(long = "input", value_name = "NAME=VALUE", value_parser = config::parse_input)]
+    inputs: Vec<(String, InputValue)>,
+    /// Log level (error, warn, info, debug or trace)
+    #[arg(long, default_value = "warn")]
+    log_level: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let level: tracing::Level = args
+        .log_level
+        .parse()
+        .with_context(|| format!("invalid log level `{}`", args.log_level))?;
+    tracing_subscriber::fmt().with_max_level(level).init();
+
+    let transcript = Transcript::load(&args.transcript)?;
+    for message in &transcript.messages {
+        println!(
+            "{:>10.3?}  step {:>3}  {:?}  {} bytes",
+            message.elapsed,
+            message.step,
+            message.direction,
+            message.data.len()
+        );
+    }
+
+    let artifact = match &args.artifact {
+        Some(path) => Arc::new(CircuitArtifact::load(path)?),
+        None => match builtin(&args.function) {
+            Some(artifact) => artifact,
+            None => bail!(
+                "`{}` is not a built-in function, give the path of its artifact",
+                args.function
+            ),
+        },
+    };
+    let inputs: BTreeMap<String, InputValue> = args.inputs.into_iter().collect();
+    let output = match args.party {
+        Replay::Evaluator => {
+            let input = bind_party_inputs(&artifact, Party::Evaluator, &inputs)?;
+            transcript::replay_evaluator(artifact.circuit(), &input, &transcript)
+        }
+        Replay::Garbler => {
+            let input = bind_party_inputs(&artifact, Party::Garbler, &inputs)?;
+            transcript::replay_garbler(artifact.circuit(), &input, &transcript)
+        }
+    }
+    .with_context(|| format!("replaying {}", args.transcript.display()))?;
+
+    println!("{}: {}", args.function, decode_output(&artifact, &output)?);
+    Ok(())
+}
@@ -1,104 +1,206 @@
+use anyhow::{bail, Context};
+use clap::Parser;
 use compute::prelude::*;
 use s2n_quic::Server;
-use server::util::extract;
-use server::util::prepare;
-use std::{error::Error, path::Path};
-use tracing::{debug, error, info, instrument};
-
-#[encrypted(compile)]
+use server::config::{self, CircuitConfig, ServerConfig};
+use server::evaluator::EvaluatorServer;
+use server::registry::PeerIdentity;
+use server::session::SessionManager;
+use server::tls;
+use server::value::bind_party_inputs;
+use std::error::Error;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+
+#[encrypted(artifact)]
 fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
     let res = a * b;
     let res = res + c;
     res - d
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    // Initialize tracing subscriber for logging
-    tracing_subscriber::fmt::init();
-
-    // Setup server
-    let mut server = Server::builder()
-        .with_tls((
-            Path::new("server/certs/cert.pem"),
-            Path::new("server/certs/key.pem"),
-        ))?
-        .with_io("127.0.0.1:4433")?
-        .start()?;
-
-    info!("Server started and listening on 127.0.0.1:4433");
-
-    while let Some(mut connection) = server.accept().await {
-        info!("Accepted new connection");
-
-        // Spawn a new task for the connection
-        tokio::spawn(async move {
-            while let Ok(Some(stream)) = connection.accept_bidirectional_stream().await {
-                info!("Accepted bidirectional stream");
-
-                // Initialize the evaluator instance with circuit and dummy input
-                let (circuit, _) = multi_arithmetic(0_u8, 0_u8, 0_u8, 0_u8);
+/// Functions built into the binary, served by name.
+fn builtin(name: &str) -> Option<Arc<CircuitArtifact>> {
+    match name {
+        "multi_arithmetic" => Some(multi_arithmetic::<u8>()),
+        _ => None,
+    }
+}
 
-                info!("Circuit: {:?}", hex::encode(circuit.blake3_hash()));
+const BUILTINS: &[&str] = &["multi_arithmetic"];
+
+/// Evaluates encrypted functions for clients over QUIC.
+#[derive(Parser, Debug)]
+struct Args {
+    /// TOML config file; flags override its settings
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Address to listen on
+    #[arg(long)]
+    listen: Option<SocketAddr>,
+    /// TLS certificate (PEM)
+    #[arg(long)]
+    cert: Option<PathBuf>,
+    /// TLS private key (PEM)
+    #[arg(long)]
+    key: Option<PathBuf>,
+    /// Require client certificates issued by this CA (PEM), enabling mutual TLS over quinn
+    #[arg(long)]
+    client_ca: Option<PathBuf>,
+    /// Function to serve, as `name` for a built-in function or `name=path` for an artifact
+    /// file; may be repeated
+    #[arg(long = "circuit", value_name = "NAME[=PATH]")]
+    circuits: Vec<String>,
+    /// Seconds to wait for the next message from a client
+    #[arg(long)]
+    idle_timeout_secs: Option<u64>,
+    /// Seconds a session may take in total
+    #[arg(long)]
+    session_timeout_secs: Option<u64>,
+    /// Maximum number of sessions running at the same time
+    #[arg(long)]
+    max_sessions: Option<usize>,
+    /// Record the protocol messages of every session to this directory
+    #[arg(long, value_name = "DIR")]
+    record_transcripts: Option<PathBuf>,
+    /// Log level (error, warn, info, debug or trace)
+    #[arg(long)]
+    log_level: Option<String>,
+}
 
-                let evaluator =
-                    GatewayEvaluator::new(&circuit, &[]).expect("Evaluator initialization failed");
+impl Args {
+    fn into_config(self) -> anyhow::Result<ServerConfig> {
+        let mut config: ServerConfig = match &self.config {
+            Some(path) => config::load(path)?,
+            None => ServerConfig::default(),
+        };
+        config.listen = self.listen.unwrap_or(config.listen);
+        config.cert = self.cert.unwrap_or(config.cert);
+        config.key = self.key.unwrap_or(config.key);
+        config.client_ca = self.client_ca.or(config.client_ca);
+        config.idle_timeout_secs = self.idle_timeout_secs.unwrap_or(config.idle_timeout_secs);
+        config.session_timeout_secs = self
+            .session_timeout_secs
+            .unwrap_or(config.session_timeout_secs);
+        config.max_sessions = self.max_sessions.unwrap_or(config.max_sessions);
+        config.transcripts = self.record_transcripts.or(config.transcripts);
+        config.log_level = self.log_level.unwrap_or(config.log_level);
+        for circuit in &self.circuits {
+            config.add_circuit(CircuitConfig::parse(circuit));
+        }
+        Ok(config)
+    }
+}
 
-                if let Err(e) = handle_evaluator_connection(evaluator, stream).await {
-                    error!("Error handling evaluator connection: {:?}", e);
-                }
-            }
-        });
+fn evaluator(circuits: &[CircuitConfig]) -> anyhow::Result<EvaluatorServer> {
+    let mut evaluator = EvaluatorServer::new();
+    if circuits.is_empty() {
+        for name in BUILTINS {
+            evaluator.register(*name, builtin(name).unwrap());
+        }
     }
 
-    Ok(())
+    for circuit in circuits {
+        if !circuit.allow.is_empty() {
+            let peers = circuit.allow.iter().map(PeerIdentity::new);
+            evaluator.restrict(&circuit.name, peers);
+        }
+        let artifact = match &circuit.artifact {
+            Some(path) => Arc::new(
+                CircuitArtifact::load(path)
+                    .with_context(|| format!("loading circuit artifact {}", path.display()))?,
+            ),
+            None => match builtin(&circuit.name) {
+                Some(artifact) => artifact,
+                None => bail!(
+                    "`{}` is not a built-in function ({}), give the path of its artifact",
+                    circuit.name,
+                    BUILTINS.join(", ")
+                ),
+            },
+        };
+
+        // the evaluator's inputs come from the config, check them before serving any client
+        let inputs = circuit.inputs.clone();
+        let provider = move |artifact: &CircuitArtifact| {
+            bind_party_inputs(artifact, Party::Evaluator, &inputs)
+        };
+        provider(artifact.as_ref())
+            .with_context(|| format!("invalid evaluator inputs for `{}`", circuit.name))?;
+        evaluator.register_with_inputs(&circuit.name, artifact, provider);
+    }
+    Ok(evaluator)
 }
 
-#[instrument(skip_all, fields(evaluator, stream))]
-async fn handle_evaluator_connection(
-    mut evaluator: GatewayEvaluator,
-    mut stream: s2n_quic::stream::BidirectionalStream,
-) -> Result<(), Box<dyn Error>> {
-    info!("Starting evaluator connection handler");
-
-    while let Some(first_chunk) = stream.receive().await? {
-        let (size, mut data) = extract(&first_chunk)?;
-        info!("size from header: {:?}", size);
-
-        // Continue receiving until the full message is obtained
-        while data.len() < size as usize {
-            let chunk = stream.receive().await?.expect("no data received");
-            data.extend_from_slice(&chunk);
+/// Runs `listener` until Ctrl-C, then stops accepting clients and waits for the sessions in
+/// flight to finish.
+async fn serve(sessions: &SessionManager, listener: impl Future<Output = ()>) {
+    tokio::select! {
+        _ = listener => {}
+        _ = tokio::signal::ctrl_c() => {
+            info!("Shutting down, waiting for {} sessions", sessions.active());
         }
+    }
+    sessions.drain().await;
+}
 
-        info!("Received data, length: {:?}", data.len());
-        debug!("Received data: {:?}", hex::encode(&data));
-
-        // Check if the evaluator has completed all steps
-        if evaluator.is_complete() {
-            info!("Evaluator processing complete, preparing output");
-            let output_message = evaluator.output(&data)?;
-            let mut output_data = Vec::new();
-            for bit in output_message {
-                output_data.push(if bit { 1 } else { 0 });
-            }
-            stream.send(prepare(output_data).into()).await?;
-            info!("Final output sent to client, closing connection");
-            break;
-        }
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let config = Args::parse().into_config()?;
+    // without mutual TLS clients are anonymous, so nobody could run a restricted function
+    if config.client_ca.is_none() && config.has_restrictions() {
+        return Err("functions limited to some clients with `allow` require `client_ca`".into());
+    }
 
-        // Process the evaluator "next" step with received data
-        let (next_evaluator, response) = evaluator.next(&data)?;
-        info!("Steps remaining: {}", next_evaluator.steps());
-        evaluator = next_evaluator;
+    // Initialize tracing subscriber for logging
+    let level: tracing::Level = config
+        .log_level
+        .parse()
+        .with_context(|| format!("invalid log level `{}`", config.log_level))?;
+    tracing_subscriber::fmt().with_max_level(level).init();
+
+    // The functions clients can select during the handshake
+    let mut evaluator = evaluator(&config.circuits)?;
+    if let Some(dir) = &config.transcripts {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        info!("Recording transcripts to {}", dir.display());
+        evaluator.record_transcripts(dir);
+    }
+    let mut functions: Vec<_> = evaluator.registry().names().collect();
+    functions.sort_unstable();
+    info!("Serving {}", functions.join(", "));
+
+    let sessions = Arc::new(SessionManager::new(
+        Arc::new(evaluator),
+        config.session_limits(),
+    ));
 
-        // Send back the response
-        info!("Sending data, length: {:?}", response.len());
-        debug!("Sending data: {:?}", hex::encode(&response));
-        stream.send(prepare(response).into()).await?;
-        info!("Sent response back to client");
+    // Setup server
+    if let Some(client_ca) = &config.client_ca {
+        // s2n-quic doesn't expose client certificates, so mutual TLS runs over quinn
+        let server = tls::load(&config.cert, &config.key)?;
+        let client_ca = std::fs::read_to_string(client_ca)
+            .with_context(|| format!("reading {}", client_ca.display()))?;
+        let tls_config = tls::server_config(&server, Some(&client_ca))?;
+        let endpoint = quinn::Endpoint::server(tls_config, config.listen)?;
+
+        info!(
+            "Server started with mutual TLS, listening on {}",
+            config.listen
+        );
+        serve(&sessions, server::quic::listen(sessions.clone(), endpoint)).await;
+    } else {
+        let server = Server::builder()
+            .with_tls((config.cert.as_path(), config.key.as_path()))?
+            .with_io(config.listen)?
+            .start()?;
+
+        info!("Server started and listening on {}", config.listen);
+        serve(&sessions, server::s2n::listen(sessions.clone(), server)).await;
     }
 
-    debug!("Evaluator connection handler complete");
     Ok(())
 }
@@ -0,0 +1,265 @@
+//! Configuration of the server and client binaries, loaded from TOML files.
+//!
+//! Every field has a default, so a config file only needs the settings that differ, and the
+//! binaries let command line flags override the file.
+
+use crate::session::SessionLimits;
+use crate::value::InputValue;
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Reads a TOML config file.
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("reading config file {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("parsing config file {}", path.display()))
+}
+
+/// A function served by the server.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CircuitConfig {
+    /// The name clients select the function by.
+    pub name: String,
+    /// A circuit artifact file to load. Without it, `name` must be a function built into the
+    /// binary.
+    pub artifact: Option<PathBuf>,
+    /// Identities of the clients allowed to run the function. Empty allows every client.
+    /// Requires `client_ca`, since clients are only identified by their certificates.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// The value of each of the evaluator's parameters, by name. Functions whose inputs all come
+    /// from the garbler don't need any.
+    #[serde(default)]
+    pub inputs: BTreeMap<String, InputValue>,
+}
+
+impl CircuitConfig {
+    /// Parses `name` or `name=path`, as accepted on the command line.
+    pub fn parse(spec: &str) -> Self {
+        match spec.split_once('=') {
+            Some((name, path)) => CircuitConfig {
+                name: name.to_string(),
+                artifact: Some(path.into()),
+                allow: vec![],
+                inputs: BTreeMap::new(),
+            },
+            None => CircuitConfig {
+                name: spec.to_string(),
+                artifact: None,
+                allow: vec![],
+                inputs: BTreeMap::new(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen: SocketAddr,
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// CA certificate that client certificates must be issued by. Enables mutual TLS, which
+    /// runs over quinn instead of s2n-quic.
+    pub client_ca: Option<PathBuf>,
+    pub log_level: String,
+    /// Seconds to wait for the next message from a client before aborting its session.
+    pub idle_timeout_secs: u64,
+    /// Seconds a session may take in total.
+    pub session_timeout_secs: u64,
+    /// Sessions running at the same time. Clients beyond that are turned away as busy.
+    pub max_sessions: usize,
+    /// Directory to record the protocol messages of every session to, for the `replay` tool.
+    /// Transcripts contain the outputs in the clear.
+    pub transcripts: Option<PathBuf>,
+    /// The functions to serve. Empty serves every function built into the binary.
+    pub circuits: Vec<CircuitConfig>,
+}
+
+impl ServerConfig {
+    /// Adds a function given on the command line. A function that is already configured keeps
+    /// its settings and only takes the artifact path, if one is given.
+    pub fn add_circuit(&mut self, circuit: CircuitConfig) {
+        match self.circuits.iter_mut().find(|c| c.name == circuit.name) {
+            Some(configured) => {
+                if circuit.artifact.is_some() {
+                    configured.artifact = circuit.artifact;
+                }
+            }
+            None => self.circuits.push(circuit),
+        }
+    }
+
+    /// Whether any function is limited to some clients.
+    pub fn has_restrictions(&self) -> bool {
+        self.circuits
+            .iter()
+            .any(|circuit| !circuit.allow.is_empty())
+    }
+
+    pub fn session_limits(&self) -> SessionLimits {
+        SessionLimits {
+            idle_timeout: Duration::from_secs(self.idle_timeout_secs),
+            total_timeout: Duration::from_secs(self.session_timeout_secs),
+            max_sessions: self.max_sessions,
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        let limits = SessionLimits::default();
+        ServerConfig {
+            listen: ([127, 0, 0, 1], 4433).into(),
+            cert: "server/certs/cert.pem".into(),
+            key: "server/certs/key.pem".into(),
+            client_ca: None,
+            log_level: "info".to_string(),
+            idle_timeout_secs: limits.idle_timeout.as_secs(),
+            session_timeout_secs: limits.total_timeout.as_secs(),
+            max_sessions: limits.max_sessions,
+            transcripts: None,
+            circuits: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+    /// Address of the server.
+    pub server: SocketAddr,
+    /// Name the server's certificate is issued for.
+    pub server_name: String,
+    /// Certificate used to authenticate the server, or the CA that issued it.
+    pub cert: PathBuf,
+    /// Client certificate and key to authenticate with, for servers that require mutual TLS.
+    pub identity_cert: Option<PathBuf>,
+    pub identity_key: Option<PathBuf>,
+    pub log_level: String,
+    /// The function to run.
+    pub function: String,
+    /// A circuit artifact file to load. Without it, `function` must be built into the binary.
+    pub artifact: Option<PathBuf>,
+    /// The value of each parameter of the function, by name.
+    pub inputs: BTreeMap<String, InputValue>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            server: ([127, 0, 0, 1], 4433).into(),
+            server_name: "localhost".to_string(),
+            cert: "server/certs/cert.pem".into(),
+            identity_cert: None,
+            identity_key: None,
+            log_level: "info".to_string(),
+            function: "multi_arithmetic".to_string(),
+            artifact: None,
+            inputs: BTreeMap::new(),
+        }
+    }
+}
+
+/// Parses a `name=value` input given on the command line.
+pub fn parse_input(spec: &str) -> Result<(String, InputValue)> {
+    let (name, value) = spec
+        .split_once('=')
+        .with_context(|| format!("expected `name=value`, got `{spec}`"))?;
+    Ok((name.trim().to_string(), value.trim().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config_uses_defaults() -> Result<()> {
+        let config: ServerConfig = toml::from_str(
+            r#"
+            listen = "0.0.0.0:5000"
+            max_sessions = 8
+
+            [[circuits]]
+            name = "multi_arithmetic"
+
+            [[circuits]]
+            name = "scoring"
+            artifact = "circuits/scoring.bin"
+            allow = ["garbler-1"]
+            "#,
+        )?;
+        assert_eq!(config.listen, "0.0.0.0:5000".parse()?);
+        assert_eq!(config.cert, ServerConfig::default().cert);
+        assert_eq!(config.session_limits().max_sessions, 8);
+        assert_eq!(
+            config.session_limits().idle_timeout,
+            SessionLimits::default().idle_timeout
+        );
+        assert_eq!(config.circuits[0], CircuitConfig::parse("multi_arithmetic"));
+        assert_eq!(
+            config.circuits[1].artifact,
+            Some(PathBuf::from("circuits/scoring.bin"))
+        );
+        assert_eq!(config.circuits[1].allow, vec!["garbler-1".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_line_circuits_merge_into_config() -> Result<()> {
+        let mut config: ServerConfig = toml::from_str(
+            r#"
+            [[circuits]]
+            name = "scoring"
+            artifact = "circuits/scoring.bin"
+            allow = ["garbler-1"]
+
+            [circuits.inputs]
+            threshold = 600
+            "#,
+        )?;
+        assert_eq!(
+            config.circuits[0].inputs["threshold"],
+            InputValue::Integer(600)
+        );
+
+        config.add_circuit(CircuitConfig::parse("scoring=circuits/scoring-v2.bin"));
+        config.add_circuit(CircuitConfig::parse("multi_arithmetic"));
+        assert_eq!(config.circuits.len(), 2);
+        assert_eq!(
+            config.circuits[0].artifact,
+            Some(PathBuf::from("circuits/scoring-v2.bin"))
+        );
+        assert_eq!(config.circuits[0].allow, vec!["garbler-1".to_string()]);
+        assert_eq!(config.circuits[1], CircuitConfig::parse("multi_arithmetic"));
+        assert!(config.has_restrictions());
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_inputs() -> Result<()> {
+        let config: ClientConfig = toml::from_str(
+            r#"
+            function = "compare"
+
+            [inputs]
+            a = 2
+            b = true
+            c = "340282366920938463463374607431768211455"
+            "#,
+        )?;
+        assert_eq!(config.inputs["a"], InputValue::Integer(2));
+        assert_eq!(config.inputs["b"], InputValue::Bool(true));
+        assert_eq!(parse_input("a = 2")?, ("a".to_string(), "2".into()));
+        assert!(parse_input("a").is_err());
+        assert!(toml::from_str::<ClientConfig>("port = 1").is_err());
+        Ok(())
+    }
+}
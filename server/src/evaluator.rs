@@ -0,0 +1,313 @@
+//! A server that evaluates any of a catalogue of circuits.
+//!
+//! Functions are registered by name together with an [`InputProvider`] for the evaluator's own
+//! inputs. Each session starts with the handshake from [`crate::handshake`], which selects the
+//! function, after which the server runs the evaluator side of the protocol on its circuit.
+//!
+//! With the `resume` feature, clients can also open resumable sessions, which the server keeps
+//! after a dropped connection until the client resumes them, see [`crate::resume`].
+//!
+//! With [`EvaluatorServer::record_transcripts`], the server records the protocol messages of
+//! every session to a file, to inspect and replay failed sessions, see [`compute::transcript`].
+
+use crate::handshake::{self, Rejection, Request};
+use crate::registry::{CircuitRegistry, PeerIdentity};
+#[cfg(feature = "resume")]
+use crate::resume::{Suspended, SuspendedSessions};
+#[cfg(feature = "resume")]
+use crate::session::SessionId;
+#[cfg(feature = "resume")]
+use anyhow::bail;
+use anyhow::{Context, Result};
+#[cfg(feature = "resume")]
+use compute::checkpoint::{self, Checkpoint};
+use compute::prelude::{CircuitArtifact, Party};
+use compute::protocol::run_evaluator;
+#[cfg(feature = "resume")]
+use compute::transport::RemoteError;
+use compute::transport::{TranscriptRecorder, Transport};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Supplies the evaluator's input bits for a session.
+///
+/// Called once per session with the artifact selected in the handshake. The bits must be laid
+/// out as described by `artifact.layout()` for `Party::Evaluator`.
+pub trait InputProvider: Send + Sync {
+    fn inputs(&self, artifact: &CircuitArtifact) -> Result<Vec<bool>>;
+}
+
+impl<F> InputProvider for F
+where
+    F: Fn(&CircuitArtifact) -> Result<Vec<bool>> + Send + Sync,
+{
+    fn inputs(&self, artifact: &CircuitArtifact) -> Result<Vec<bool>> {
+        self(artifact)
+    }
+}
+
+/// Provider for functions whose inputs all come from the garbler.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoInputs;
+
+impl InputProvider for NoInputs {
+    fn inputs(&self, _artifact: &CircuitArtifact) -> Result<Vec<bool>> {
+        Ok(vec![])
+    }
+}
+
+/// The outcome of a session served by an [`EvaluatorServer`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    /// The function the client selected.
+    pub function: String,
+    /// The authenticated identity of the client, if any.
+    pub peer: Option<PeerIdentity>,
+    /// The output of the circuit, which was also sent to the client.
+    pub output: Vec<bool>,
+}
+
+/// Evaluates the registered functions for any client that holds the same circuit artifacts.
+#[derive(Clone, Default)]
+pub struct EvaluatorServer {
+    registry: CircuitRegistry,
+    providers: HashMap<String, Arc<dyn InputProvider>>,
+    #[cfg(feature = "resume")]
+    suspended: SuspendedSessions,
+    transcripts: Option<PathBuf>,
+}
+
+impl EvaluatorServer {
+    pub fn new() -> Self {
+        EvaluatorServer::default()
+    }
+
+    /// Registers a function whose inputs are all provided by the garbler, e.g. the artifact
+    /// returned by an `#[encrypted(artifact)]` function.
+    pub fn register(&mut self, name: impl Into<String>, artifact: Arc<CircuitArtifact>) {
+        self.register_with_inputs(name, artifact, NoInputs);
+    }
+
+    /// Registers a function for which `provider` supplies the evaluator's inputs. Replaces any
+    /// function registered under the same name before.
+    pub fn register_with_inputs(
+        &mut self,
+        name: impl Into<String>,
+        artifact: Arc<CircuitArtifact>,
+        provider: impl InputProvider + 'static,
+    ) {
+        let name = name.into();
+        self.providers.insert(name.clone(), Arc::new(provider));
+        self.registry.register(name, artifact);
+    }
+
+    /// Loads a circuit artifact saved with `CircuitArtifact::save` and registers it under
+    /// `name`, with inputs from `provider`.
+    pub fn register_file(
+        &mut self,
+        name: impl Into<String>,
+        path: impl AsRef<Path>,
+        provider: impl InputProvider + 'static,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let artifact = CircuitArtifact::load(path)
+            .with_context(|| format!("loading circuit artifact {}", path.display()))?;
+        self.register_with_inputs(name, Arc::new(artifact), provider);
+        Ok(())
+    }
+
+    /// Only lets the clients in `peers` run the function `name`.
+    pub fn restrict<I>(&mut self, name: impl Into<String>, peers: I)
+    where
+        I: IntoIterator<Item = PeerIdentity>,
+    {
+        self.registry.restrict(name, peers);
+    }
+
+    pub fn registry(&self) -> &CircuitRegistry {
+        &self.registry
+    }
+
+    /// Keeps resumable sessions for `window` after their connection dropped. Defaults to five
+    /// minutes.
+    #[cfg(feature = "resume")]
+    pub fn set_resume_window(&mut self, window: std::time::Duration) {
+        self.suspended = SuspendedSessions::new(window);
+    }
+
+    /// Resumable sessions waiting for their client to reconnect.
+    #[cfg(feature = "resume")]
+    pub fn suspended(&self) -> &SuspendedSessions {
+        &self.suspended
+    }
+
+    /// Records the protocol messages of every session to a new file in `dir`, named after the
+    /// function and the time the session started. Transcripts contain the outputs in the clear,
+    /// see [`compute::transcript`]. Resumable sessions aren't recorded.
+    pub fn record_transcripts(&mut self, dir: impl Into<PathBuf>) {
+        self.transcripts = Some(dir.into());
+    }
+
+    /// Serves a single session on `transport`: negotiates the function with the client and runs
+    /// the evaluator side of the protocol on its circuit.
+    pub async fn serve<T>(&self, transport: &mut T) -> Result<Session>
+    where
+        T: Transport + ?Sized,
+    {
+        self.serve_peer(transport, None).await
+    }
+
+    /// Like [`EvaluatorServer::serve`], for a client authenticated as `peer`, which may run the
+    /// functions restricted to it.
+    pub async fn serve_peer<T>(
+        &self,
+        transport: &mut T,
+        peer: Option<&PeerIdentity>,
+    ) -> Result<Session>
+    where
+        T: Transport + ?Sized,
+    {
+        let request = handshake::accept_request(transport, &self.registry, peer).await?;
+        let (function, artifact) = match request {
+            Request::Run { function, artifact } => (function, artifact),
+            #[cfg(feature = "resume")]
+            Request::Open { function, artifact } => {
+                let input = self.inputs(&function, &artifact)?;
+                let suspended = Suspended {
+                    checkpoint: Checkpoint::evaluator(artifact.circuit(), &input)?,
+                    function,
+                    artifact,
+                    peer: peer.cloned(),
+                };
+                return self
+                    .run_resumable(transport, SessionId::random(), suspended)
+                    .await;
+            }
+            #[cfg(feature = "resume")]
+            Request::Resume(id) => match self.suspended.take(id, peer).await {
+                Some(suspended) => return self.run_resumable(transport, id, suspended).await,
+                None => {
+                    return handshake::refuse(transport, Rejection::UnknownSession(id))
+                        .await
+                        .map_err(Into::into)
+                }
+            },
+            #[cfg(not(feature = "resume"))]
+            Request::Open { .. } | Request::Resume(_) => {
+                return handshake::refuse(transport, Rejection::NotResumable)
+                    .await
+                    .map_err(Into::into)
+            }
+        };
+
+        let input = self.inputs(&function, &artifact)?;
+        info!(function = %function, "evaluating");
+        let output = match &self.transcripts {
+            Some(dir) => {
+                let path = dir.join(transcript_name(&function));
+                info!(path = %path.display(), "recording transcript");
+                let mut transport =
+                    TranscriptRecorder::create(transport, Party::Evaluator, &path).await?;
+                run_evaluator(&mut transport, artifact.circuit(), &input).await?
+            }
+            None => run_evaluator(transport, artifact.circuit(), &input).await?,
+        };
+        Ok(Session {
+            function,
+            peer: peer.cloned(),
+            output,
+        })
+    }
+
+    /// The evaluator's inputs for a session of `function`.
+    fn inputs(&self, function: &str, artifact: &CircuitArtifact) -> Result<Vec<bool>> {
+        let provider = self
+            .providers
+            .get(function)
+            .with_context(|| format!("no input provider for `{function}`"))?;
+        let input = provider
+            .inputs(artifact)
+            .with_context(|| format!("providing the evaluator inputs of `{function}`"))?;
+        artifact
+            .layout()
+            .check(Party::Evaluator, &input)
+            .with_context(|| format!("input provider of `{function}` returned invalid inputs"))?;
+        Ok(input)
+    }
+
+    /// Accepts the resumable session `id` and runs it from its checkpoint. If the connection
+    /// drops, the session is suspended until the client resumes it.
+    #[cfg(feature = "resume")]
+    async fn run_resumable<T>(
+        &self,
+        transport: &mut T,
+        id: SessionId,
+        mut session: Suspended,
+    ) -> Result<Session>
+    where
+        T: Transport + ?Sized,
+    {
+        let mut registration = self.suspended.register(id, session.peer.clone());
+        let run = async {
+            handshake::opened(transport, id, session.artifact.blake3_hash()).await?;
+            info!(
+                function = %session.function,
+                session = %id,
+                received = session.checkpoint.received(),
+                "evaluating resumable session"
+            );
+            checkpoint::run_evaluator(
+                transport,
+                session.artifact.circuit(),
+                &mut session.checkpoint,
+            )
+            .await
+        };
+        let result = tokio::select! {
+            result = run => result,
+            Ok(resumer) = registration.handover() => {
+                // the client reconnected before this connection was noticed to be lost
+                let _ = resumer.send(session);
+                bail!("session {id} was resumed on another connection");
+            }
+        };
+
+        // dropping the registration forgets the session
+        match result {
+            Ok(output) => Ok(Session {
+                function: session.function,
+                peer: session.peer,
+                output,
+            }),
+            // the client gave up on the session if it aborted it itself
+            Err(error) if error.downcast_ref::<RemoteError>().is_some() => Err(error),
+            Err(error) => {
+                info!(session = %id, "suspending session until the client resumes it");
+                registration.suspend(session);
+                Err(error)
+            }
+        }
+    }
+}
+
+/// A unique file name for the transcript of a session of `function`.
+fn transcript_name(function: &str) -> String {
+    let function: String = function
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    format!(
+        "{function}-{started}-{:08x}.transcript",
+        rand::random::<u32>()
+    )
+}
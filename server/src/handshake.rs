@@ -0,0 +1,365 @@
+//! Negotiates which circuit a session evaluates before the protocol starts.
+//!
+//! The client (garbler) announces the function it wants to run together with the blake3 hash of
+//! its circuit artifact. The server looks the function up in its [`CircuitRegistry`], by name or,
+//! if the name is empty, by hash, and accepts only if both sides hold the same artifact.
+//! Otherwise it replies with the reason and both sides abort the session.
+//!
+//! With [`open`] instead of [`announce`], the client asks for a session that survives a dropped
+//! connection. The server replies with the ID of the session, which the client passes to
+//! [`resume`] on a new connection. Servers built without the `resume` feature refuse both.
+
+use crate::registry::{CircuitRegistry, PeerIdentity};
+use crate::session::SessionId;
+use compute::prelude::CircuitArtifact;
+use compute::transport::Transport;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum HandshakeMessage {
+    /// Sent by the client to select a function.
+    Hello {
+        function: String,
+        circuit_hash: [u8; 32],
+    },
+    /// The server holds the same circuit and is ready to run the protocol.
+    Accept { circuit_hash: [u8; 32] },
+    /// The server refuses the session.
+    Reject(Rejection),
+    /// Sent by the client instead of `Hello` to select a function for a resumable session.
+    Open {
+        function: String,
+        circuit_hash: [u8; 32],
+    },
+    /// Sent by the client to continue a resumable session on a new connection.
+    Resume { session: SessionId },
+    /// The server accepts `Open` or `Resume`, with the ID to resume the session by.
+    Opened {
+        session: SessionId,
+        circuit_hash: [u8; 32],
+    },
+}
+
+/// Why the server refused a session.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Error)]
+pub enum Rejection {
+    #[error("unknown function `{0}`")]
+    UnknownFunction(String),
+    #[error("no circuit with hash {}", hex::encode(.0))]
+    UnknownCircuit([u8; 32]),
+    #[error(
+        "circuit hash mismatch for `{function}`: server has {}, client announced {}",
+        hex::encode(.expected),
+        hex::encode(.announced)
+    )]
+    HashMismatch {
+        function: String,
+        expected: [u8; 32],
+        announced: [u8; 32],
+    },
+    /// Only reported on the server: the client is told that the function doesn't exist.
+    #[error("not allowed to run `{function}`")]
+    Forbidden { function: String },
+    #[error("no session {0} to resume")]
+    UnknownSession(SessionId),
+    #[error("the server doesn't support resumable sessions")]
+    NotResumable,
+}
+
+#[derive(Debug, Error)]
+pub enum HandshakeError {
+    #[error("server rejected the session: {0}")]
+    Rejected(Rejection),
+    #[error("session refused: {0}")]
+    Refused(Rejection),
+    #[error("unexpected handshake message {0:?}")]
+    UnexpectedMessage(HandshakeMessage),
+    #[error("malformed handshake message: {0}")]
+    Malformed(#[from] bincode::Error),
+    #[error(transparent)]
+    Transport(#[from] anyhow::Error),
+}
+
+/// Client side: announces `function` with the hash of its circuit artifact and waits for the
+/// server to accept.
+pub async fn announce<T>(
+    transport: &mut T,
+    function: &str,
+    circuit_hash: [u8; 32],
+) -> Result<(), HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    send(
+        transport,
+        &HandshakeMessage::Hello {
+            function: function.to_string(),
+            circuit_hash,
+        },
+    )
+    .await?;
+
+    match receive(transport).await? {
+        HandshakeMessage::Accept {
+            circuit_hash: accepted,
+        } if accepted == circuit_hash => Ok(()),
+        HandshakeMessage::Accept {
+            circuit_hash: accepted,
+        } => Err(HandshakeError::Rejected(Rejection::HashMismatch {
+            function: function.to_string(),
+            expected: accepted,
+            announced: circuit_hash,
+        })),
+        HandshakeMessage::Reject(rejection) => Err(HandshakeError::Rejected(rejection)),
+        other => Err(HandshakeError::UnexpectedMessage(other)),
+    }
+}
+
+/// Client side: like [`announce`], for a resumable session. Returns the ID to [`resume`] the
+/// session by.
+pub async fn open<T>(
+    transport: &mut T,
+    function: &str,
+    circuit_hash: [u8; 32],
+) -> Result<SessionId, HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    send(
+        transport,
+        &HandshakeMessage::Open {
+            function: function.to_string(),
+            circuit_hash,
+        },
+    )
+    .await?;
+    opened_with(transport, function, circuit_hash).await
+}
+
+/// Client side: continues the resumable `session` of the function with `circuit_hash` on a new
+/// connection.
+pub async fn resume<T>(
+    transport: &mut T,
+    session: SessionId,
+    circuit_hash: [u8; 32],
+) -> Result<(), HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    send(transport, &HandshakeMessage::Resume { session }).await?;
+    match opened_with(transport, "", circuit_hash).await? {
+        resumed if resumed == session => Ok(()),
+        resumed => Err(HandshakeError::UnexpectedMessage(
+            HandshakeMessage::Opened {
+                session: resumed,
+                circuit_hash,
+            },
+        )),
+    }
+}
+
+async fn opened_with<T>(
+    transport: &mut T,
+    function: &str,
+    circuit_hash: [u8; 32],
+) -> Result<SessionId, HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    match receive(transport).await? {
+        HandshakeMessage::Opened {
+            session,
+            circuit_hash: accepted,
+        } if accepted == circuit_hash => Ok(session),
+        HandshakeMessage::Opened {
+            circuit_hash: accepted,
+            ..
+        } => Err(HandshakeError::Rejected(Rejection::HashMismatch {
+            function: function.to_string(),
+            expected: accepted,
+            announced: circuit_hash,
+        })),
+        HandshakeMessage::Reject(rejection) => Err(HandshakeError::Rejected(rejection)),
+        other => Err(HandshakeError::UnexpectedMessage(other)),
+    }
+}
+
+/// What the client asked for in the handshake, see [`accept_request`].
+pub enum Request {
+    /// A session running `function`, which was accepted.
+    Run {
+        function: String,
+        artifact: Arc<CircuitArtifact>,
+    },
+    /// A resumable session running `function`. The client may run it, but the server still has
+    /// to accept with [`opened`] or refuse with [`refuse`].
+    Open {
+        function: String,
+        artifact: Arc<CircuitArtifact>,
+    },
+    /// Continuing a resumable session, which the server still has to look up and accept with
+    /// [`opened`] or refuse with [`refuse`].
+    Resume(SessionId),
+}
+
+/// Server side: waits for the client to select a function and returns its name and circuit if
+/// the client holds the same artifact and `peer`, the authenticated identity of the client if
+/// any, may run it. Otherwise the client is told why and an error returned. Resumable sessions
+/// are refused, see [`accept_request`].
+pub async fn accept<T>(
+    transport: &mut T,
+    registry: &CircuitRegistry,
+    peer: Option<&PeerIdentity>,
+) -> Result<(String, Arc<CircuitArtifact>), HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    match accept_request(transport, registry, peer).await? {
+        Request::Run { function, artifact } => Ok((function, artifact)),
+        Request::Open { .. } | Request::Resume(_) => {
+            refuse(transport, Rejection::NotResumable).await
+        }
+    }
+}
+
+/// Server side: like [`accept`], but also lets the client open or resume a resumable session.
+pub async fn accept_request<T>(
+    transport: &mut T,
+    registry: &CircuitRegistry,
+    peer: Option<&PeerIdentity>,
+) -> Result<Request, HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    match receive(transport).await? {
+        HandshakeMessage::Hello {
+            function,
+            circuit_hash,
+        } => {
+            let (function, artifact) =
+                select(transport, registry, peer, function, circuit_hash).await?;
+            send(
+                transport,
+                &HandshakeMessage::Accept {
+                    circuit_hash: artifact.blake3_hash(),
+                },
+            )
+            .await?;
+            info!(
+                function = %function,
+                hash = %hex::encode(circuit_hash),
+                peer = ?peer.map(PeerIdentity::as_str),
+                "session accepted"
+            );
+            Ok(Request::Run { function, artifact })
+        }
+        HandshakeMessage::Open {
+            function,
+            circuit_hash,
+        } => {
+            let (function, artifact) =
+                select(transport, registry, peer, function, circuit_hash).await?;
+            Ok(Request::Open { function, artifact })
+        }
+        HandshakeMessage::Resume { session } => Ok(Request::Resume(session)),
+        other => Err(HandshakeError::UnexpectedMessage(other)),
+    }
+}
+
+/// Server side: accepts a resumable session, which the client can resume by `session`.
+pub async fn opened<T>(
+    transport: &mut T,
+    session: SessionId,
+    circuit_hash: [u8; 32],
+) -> Result<(), HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    send(
+        transport,
+        &HandshakeMessage::Opened {
+            session,
+            circuit_hash,
+        },
+    )
+    .await
+}
+
+/// Looks up the function the client announced, refusing the session if it can't run it.
+async fn select<T>(
+    transport: &mut T,
+    registry: &CircuitRegistry,
+    peer: Option<&PeerIdentity>,
+    function: String,
+    announced: [u8; 32],
+) -> Result<(String, Arc<CircuitArtifact>), HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    let found = if function.is_empty() {
+        registry
+            .find_by_hash_for(&announced, peer)
+            .map(|(name, artifact)| (name.to_string(), artifact.clone()))
+            .ok_or(Rejection::UnknownCircuit(announced))
+    } else {
+        registry
+            .get(&function)
+            .map(|artifact| (function.clone(), artifact.clone()))
+            .ok_or_else(|| Rejection::UnknownFunction(function.clone()))
+    };
+
+    match found {
+        Ok((name, _)) if !registry.is_allowed(&name, peer) => {
+            // the client is told the function doesn't exist, so that it can't find out which
+            // functions are registered by asking for ones it isn't allowed to run
+            warn!(function = %name, ?peer, "client isn't allowed to run the function");
+            let reply = if function.is_empty() {
+                Rejection::UnknownCircuit(announced)
+            } else {
+                Rejection::UnknownFunction(function)
+            };
+            send(transport, &HandshakeMessage::Reject(reply)).await?;
+            Err(HandshakeError::Refused(Rejection::Forbidden {
+                function: name,
+            }))
+        }
+        Ok((name, artifact)) if artifact.blake3_hash() != announced => {
+            let rejection = Rejection::HashMismatch {
+                function: name,
+                expected: artifact.blake3_hash(),
+                announced,
+            };
+            refuse(transport, rejection).await
+        }
+        Ok(found) => Ok(found),
+        Err(rejection) => refuse(transport, rejection).await,
+    }
+}
+
+/// Server side: tells the client why the session is refused, and returns the matching error.
+pub async fn refuse<T, R>(transport: &mut T, rejection: Rejection) -> Result<R, HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    send(transport, &HandshakeMessage::Reject(rejection.clone())).await?;
+    Err(HandshakeError::Refused(rejection))
+}
+
+async fn send<T>(transport: &mut T, message: &HandshakeMessage) -> Result<(), HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    transport.send(bincode::serialize(message)?).await?;
+    Ok(())
+}
+
+async fn receive<T>(transport: &mut T) -> Result<HandshakeMessage, HandshakeError>
+where
+    T: Transport + ?Sized,
+{
+    let data = transport.receive().await?;
+    Ok(bincode::deserialize(&data)?)
+}
@@ -1 +1,13 @@
+pub mod config;
+pub mod evaluator;
+pub mod handshake;
+pub mod quic;
+pub mod registry;
+#[cfg(feature = "resume")]
+pub mod resume;
+#[cfg(feature = "s2n-quic")]
+pub mod s2n;
+pub mod session;
+pub mod tls;
 pub mod util;
+pub mod value;
@@ -0,0 +1,46 @@
+use crate::session::SessionManager;
+use crate::tls::peer_identity;
+use compute::transport::QuicTransport;
+use quinn::Endpoint;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// Accepts connections on `endpoint` until it is closed or `sessions` shuts down, and runs
+/// every bidirectional stream as an independent session on behalf of the client identity the
+/// connection was authenticated with.
+pub async fn listen(sessions: Arc<SessionManager>, endpoint: Endpoint) {
+    loop {
+        let incoming = tokio::select! {
+            incoming = endpoint.accept() => match incoming {
+                Some(incoming) => incoming,
+                None => break,
+            },
+            _ = sessions.shutting_down() => break,
+        };
+
+        let sessions = sessions.clone();
+        tokio::spawn(async move {
+            let connection = match incoming.await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    error!("Error accepting connection: {:?}", e);
+                    return;
+                }
+            };
+            let peer = peer_identity(&connection);
+            info!(peer = ?peer, "Accepted new connection");
+
+            loop {
+                let transport = tokio::select! {
+                    transport = QuicTransport::accept(&connection) => match transport {
+                        Ok(transport) => transport,
+                        Err(_) => break,
+                    },
+                    _ = sessions.shutting_down() => break,
+                };
+                info!("Accepted bidirectional stream");
+                sessions.spawn(transport, peer.clone());
+            }
+        });
+    }
+}
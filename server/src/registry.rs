@@ -0,0 +1,127 @@
+use compute::prelude::CircuitArtifact;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+/// The identity of an authenticated client, e.g. the common name of its TLS certificate.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PeerIdentity(String);
+
+impl PeerIdentity {
+    pub fn new(name: impl Into<String>) -> Self {
+        PeerIdentity(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PeerIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The circuits a server can evaluate, by function name.
+#[derive(Clone, Default)]
+pub struct CircuitRegistry {
+    circuits: HashMap<String, Arc<CircuitArtifact>>,
+    /// The names each artifact is registered under, by hash, in a stable order.
+    names_by_hash: HashMap<[u8; 32], BTreeSet<String>>,
+    /// Functions that only the listed clients may run. Functions without an entry are open to
+    /// every client.
+    allowed: HashMap<String, HashSet<PeerIdentity>>,
+}
+
+impl CircuitRegistry {
+    pub fn new() -> Self {
+        CircuitRegistry::default()
+    }
+
+    /// Makes `artifact` available under `name`, replacing any circuit registered under the same
+    /// name before.
+    pub fn register(&mut self, name: impl Into<String>, artifact: Arc<CircuitArtifact>) {
+        let name = name.into();
+        self.names_by_hash
+            .entry(artifact.blake3_hash())
+            .or_default()
+            .insert(name.clone());
+        if let Some(replaced) = self.circuits.insert(name.clone(), artifact) {
+            self.unindex(&replaced.blake3_hash(), &name);
+        }
+    }
+
+    fn unindex(&mut self, hash: &[u8; 32], name: &str) {
+        if self
+            .circuits
+            .get(name)
+            .map(|artifact| artifact.blake3_hash())
+            == Some(*hash)
+        {
+            return;
+        }
+        if let Some(names) = self.names_by_hash.get_mut(hash) {
+            names.remove(name);
+            if names.is_empty() {
+                self.names_by_hash.remove(hash);
+            }
+        }
+    }
+
+    /// Only lets the clients in `peers` run the function `name`. Unauthenticated clients are
+    /// refused.
+    pub fn restrict<I>(&mut self, name: impl Into<String>, peers: I)
+    where
+        I: IntoIterator<Item = PeerIdentity>,
+    {
+        self.allowed.entry(name.into()).or_default().extend(peers);
+    }
+
+    /// Whether `peer` may run the function `name`.
+    pub fn is_allowed(&self, name: &str, peer: Option<&PeerIdentity>) -> bool {
+        match self.allowed.get(name) {
+            Some(allowed) => peer.is_some_and(|peer| allowed.contains(peer)),
+            None => true,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<CircuitArtifact>> {
+        self.circuits.get(name)
+    }
+
+    /// Looks up a circuit by the blake3 hash of its artifact. If the artifact is registered
+    /// under several names, returns the first name in lexicographic order.
+    pub fn find_by_hash(&self, hash: &[u8; 32]) -> Option<(&str, &Arc<CircuitArtifact>)> {
+        let name = self.names_by_hash.get(hash)?.first()?;
+        Some((name.as_str(), &self.circuits[name]))
+    }
+
+    /// Like [`CircuitRegistry::find_by_hash`], preferring the first name `peer` may run, so
+    /// that the access decision doesn't depend on which alias of the artifact is picked.
+    pub fn find_by_hash_for(
+        &self,
+        hash: &[u8; 32],
+        peer: Option<&PeerIdentity>,
+    ) -> Option<(&str, &Arc<CircuitArtifact>)> {
+        let names = self.names_by_hash.get(hash)?;
+        let name = names
+            .iter()
+            .find(|name| self.is_allowed(name, peer))
+            .or_else(|| names.first())?;
+        Some((name.as_str(), &self.circuits[name]))
+    }
+
+    /// Names of all registered functions.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.circuits.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.circuits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.circuits.is_empty()
+    }
+}
@@ -0,0 +1,274 @@
+//! Resumable sessions that survive a dropped connection.
+//!
+//! A client opens a resumable session with [`handshake::open`](crate::handshake::open), and both
+//! parties run the protocol from a [`Checkpoint`]. When the connection drops, the
+//! [`EvaluatorServer`](crate::evaluator::EvaluatorServer) keeps the checkpoint of the session
+//! for a while, and the client continues it on a new connection with
+//! [`handshake::resume`](crate::handshake::resume), see [`run_garbler`].
+//!
+//! Suspended sessions hold the evaluator's input and randomness, see the security notes in
+//! [`compute::checkpoint`]. They are kept in memory only, for at most the resume window, and can
+//! only be resumed once and by the client that opened them.
+//!
+//! A session is forgotten as soon as the evaluator completes it. If the connection drops after
+//! that, before the garbler received the last message, resuming the session fails with
+//! [`Rejection::UnknownSession`](crate::handshake::Rejection::UnknownSession) and the garbler
+//! has to run the function again in a new session.
+
+use crate::handshake::{self, HandshakeError};
+use crate::registry::PeerIdentity;
+use crate::session::SessionId;
+use anyhow::{Context, Result};
+use compute::checkpoint::{self, Checkpoint};
+use compute::prelude::CircuitArtifact;
+use compute::transport::{ErrorKind, RemoteError, Transport};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+/// A resumable session, while it waits for its client to reconnect.
+pub struct Suspended {
+    pub function: String,
+    pub artifact: Arc<CircuitArtifact>,
+    pub peer: Option<PeerIdentity>,
+    pub checkpoint: Checkpoint,
+}
+
+/// Receives the request to hand a running session over to a new connection, see
+/// [`SuspendedSessions::take`].
+pub type Handover = oneshot::Receiver<oneshot::Sender<Suspended>>;
+
+enum Entry {
+    /// The session runs on a connection that may already be lost without the server noticing.
+    Running {
+        peer: Option<PeerIdentity>,
+        handover: oneshot::Sender<oneshot::Sender<Suspended>>,
+        /// Tells this run apart from a later run of the same session on another connection.
+        run: u64,
+    },
+    Suspended {
+        session: Suspended,
+        since: Instant,
+    },
+}
+
+type Sessions = Mutex<HashMap<SessionId, Entry>>;
+
+/// The resumable sessions of a server, by ID.
+#[derive(Clone)]
+pub struct SuspendedSessions {
+    sessions: Arc<Sessions>,
+    runs: Arc<AtomicU64>,
+    window: Duration,
+}
+
+impl Default for SuspendedSessions {
+    fn default() -> Self {
+        SuspendedSessions::new(Duration::from_secs(300))
+    }
+}
+
+impl SuspendedSessions {
+    /// Keeps suspended sessions for `window` after their connection dropped.
+    pub fn new(window: Duration) -> Self {
+        SuspendedSessions {
+            sessions: Arc::default(),
+            runs: Arc::default(),
+            window,
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Registers the running session `id` of `peer`. The session has to give itself up when
+    /// [`Registration::handover`] resolves, and is ended with [`Registration::suspend`] or by
+    /// dropping the registration.
+    pub fn register(&self, id: SessionId, peer: Option<PeerIdentity>) -> Registration {
+        let (handover, requests) = oneshot::channel();
+        let run = self.runs.fetch_add(1, Ordering::Relaxed);
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.insert(
+            id,
+            Entry::Running {
+                peer,
+                handover,
+                run,
+            },
+        );
+        Registration {
+            sessions: self.clone(),
+            id,
+            run,
+            handover: requests,
+        }
+    }
+
+    /// Removes and returns the session `id` if it was opened by `peer` and hasn't expired. If
+    /// the session is still running, because the server hasn't noticed yet that its connection
+    /// dropped, the session is stopped and handed over.
+    pub async fn take(&self, id: SessionId, peer: Option<&PeerIdentity>) -> Option<Suspended> {
+        let session = {
+            let mut sessions = self.sessions.lock().unwrap();
+            prune(&mut sessions, self.window);
+            let valid = match sessions.get(&id) {
+                Some(Entry::Running {
+                    peer: opened_by, ..
+                }) => opened_by.as_ref() == peer,
+                Some(Entry::Suspended { session, .. }) => session.peer.as_ref() == peer,
+                None => false,
+            };
+            if !valid {
+                return None;
+            }
+            match sessions.remove(&id)? {
+                Entry::Suspended { session, .. } => return Some(session),
+                Entry::Running { handover, .. } => {
+                    let (resumer, session) = oneshot::channel();
+                    handover.send(resumer).ok()?;
+                    session
+                }
+            }
+        };
+        session.await.ok()
+    }
+
+    /// Number of resumable sessions, running or suspended.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Removes the suspended sessions whose resume window expired.
+fn prune(sessions: &mut HashMap<SessionId, Entry>, window: Duration) {
+    sessions.retain(|_, entry| match entry {
+        Entry::Suspended { since, .. } => since.elapsed() < window,
+        Entry::Running { .. } => true,
+    });
+}
+
+/// A running resumable session, from [`SuspendedSessions::register`]. Dropping it forgets the
+/// session, so a run that completes, fails for good or is cancelled doesn't leave it behind.
+pub struct Registration {
+    sessions: SuspendedSessions,
+    id: SessionId,
+    run: u64,
+    handover: Handover,
+}
+
+impl Registration {
+    /// Resolves when a new connection asks for the session, see [`SuspendedSessions::take`].
+    pub fn handover(&mut self) -> &mut Handover {
+        &mut self.handover
+    }
+
+    /// Keeps the session after its connection dropped, until it is resumed or the window
+    /// expires. If a new connection asked for the session in the meantime, it is handed over
+    /// right away.
+    pub fn suspend(mut self, session: Suspended) {
+        let window = self.sessions.window;
+        let mut sessions = self.sessions.sessions.lock().unwrap();
+        if let Ok(resumer) = self.handover.try_recv() {
+            let _ = resumer.send(session);
+            return;
+        }
+        prune(&mut sessions, window);
+        sessions.insert(
+            self.id,
+            Entry::Suspended {
+                session,
+                since: Instant::now(),
+            },
+        );
+
+        // forget the session once the window expires, even if nothing else touches the sessions
+        let expired = Arc::downgrade(&self.sessions.sessions);
+        tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            if let Some(sessions) = expired.upgrade() {
+                prune(&mut sessions.lock().unwrap(), window);
+            }
+        });
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let mut sessions = self.sessions.sessions.lock().unwrap();
+        // leave the session alone if it was suspended, or handed over to another run
+        if let Some(Entry::Running { run, .. }) = sessions.get(&self.id) {
+            if *run == self.run {
+                sessions.remove(&self.id);
+            }
+        }
+    }
+}
+
+/// Whether a failed run may be resumed: anything but the other party deliberately aborting.
+fn is_resumable(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<RemoteError>() {
+        Some(error) => error.kind == ErrorKind::Timeout,
+        None => !matches!(
+            error.downcast_ref::<HandshakeError>(),
+            Some(HandshakeError::Rejected(_) | HandshakeError::Refused(_))
+        ),
+    }
+}
+
+/// Client side: runs `function` with `input` in a resumable session. Connects with `connect`,
+/// and after the connection drops reconnects and resumes the session, up to `retries` times.
+///
+/// Fails with [`HandshakeError::Rejected`] if the connection dropped after the evaluator already
+/// completed the session, see the [module documentation](self).
+pub async fn run_garbler<C, F, T>(
+    mut connect: C,
+    function: &str,
+    artifact: &CircuitArtifact,
+    input: &[bool],
+    retries: usize,
+) -> Result<Vec<bool>>
+where
+    C: FnMut() -> F,
+    F: Future<Output = Result<T>>,
+    T: Transport,
+{
+    let circuit = artifact.circuit();
+    let mut checkpoint = Checkpoint::garbler(circuit, input)?;
+    let mut transport = connect().await?;
+    let session = handshake::open(&mut transport, function, artifact.blake3_hash()).await?;
+    info!(%session, "opened resumable session");
+
+    let mut retries_left = retries;
+    loop {
+        let error = match checkpoint::run_garbler(&mut transport, circuit, &mut checkpoint).await {
+            Ok(output) => return Ok(output),
+            Err(error) => error,
+        };
+
+        // reconnect until the session is resumed, or the retries are used up
+        let mut error = Some(error);
+        while let Some(failed) = error.take() {
+            if retries_left == 0 || !is_resumable(&failed) {
+                return Err(failed).with_context(|| format!("resumable session {session}"));
+            }
+            retries_left -= 1;
+            warn!(%session, "connection lost, resuming: {:#}", failed);
+
+            let resumed = async {
+                transport = connect().await?;
+                handshake::resume(&mut transport, session, artifact.blake3_hash()).await?;
+                anyhow::Ok(())
+            };
+            error = resumed.await.err();
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::session::SessionManager;
+use compute::transport::StreamTransport;
+use s2n_quic::stream::{BidirectionalStream, ReceiveStream, SendStream};
+use s2n_quic::Server;
+use std::sync::Arc;
+use tracing::info;
+
+/// A transport over a bidirectional s2n-quic stream.
+pub type S2nTransport = StreamTransport<ReceiveStream, SendStream>;
+
+/// Wraps an s2n-quic stream so the protocol drivers in `compute::protocol` can run over it.
+pub fn transport(stream: BidirectionalStream) -> S2nTransport {
+    let (receive, send) = stream.split();
+    StreamTransport::new(receive, send)
+}
+
+/// Accepts connections on `server` until it is closed or `sessions` shuts down, and runs every
+/// bidirectional stream as an independent session.
+pub async fn listen(sessions: Arc<SessionManager>, mut server: Server) {
+    loop {
+        let mut connection = tokio::select! {
+            connection = server.accept() => match connection {
+                Some(connection) => connection,
+                None => break,
+            },
+            _ = sessions.shutting_down() => break,
+        };
+        info!("Accepted new connection");
+
+        let sessions = sessions.clone();
+        tokio::spawn(async move {
+            loop {
+                let stream = tokio::select! {
+                    stream = connection.accept_bidirectional_stream() => match stream {
+                        Ok(Some(stream)) => stream,
+                        _ => break,
+                    },
+                    _ = sessions.shutting_down() => break,
+                };
+                info!("Accepted bidirectional stream");
+                sessions.spawn(transport(stream), None);
+            }
+        });
+    }
+}
@@ -0,0 +1,277 @@
+//! Bounded, cancellable sessions of an [`EvaluatorServer`].
+//!
+//! The [`SessionManager`] gives every session an ID, limits how many run at once, aborts
+//! sessions whose client stops responding or that run for too long, and drains the sessions in
+//! flight on shutdown. Whenever it aborts a session it tells the client why with a
+//! [`RemoteError`] instead of just closing the stream.
+
+use crate::evaluator::{EvaluatorServer, Session};
+use crate::handshake::HandshakeError;
+use crate::registry::PeerIdentity;
+use anyhow::Result;
+use async_trait::async_trait;
+use compute::transport::{ErrorKind, RemoteError, Transport};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{info, info_span, warn, Instrument};
+
+/// Identifies a session in logs and in the replies to the client. IDs are random, so they
+/// can't be guessed by other clients.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SessionId(u128);
+
+impl SessionId {
+    pub fn random() -> Self {
+        SessionId(rand::random())
+    }
+}
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// Limits applied to every session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionLimits {
+    /// How long to wait for the next message from the client.
+    pub idle_timeout: Duration,
+    /// How long a session may take from the handshake to the output.
+    pub total_timeout: Duration,
+    /// Sessions running at the same time. Clients beyond that are turned away as busy.
+    pub max_sessions: usize,
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        SessionLimits {
+            idle_timeout: Duration::from_secs(30),
+            total_timeout: Duration::from_secs(600),
+            max_sessions: 64,
+        }
+    }
+}
+
+/// Why the manager ended a session.
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("no message from the client for {0:?}")]
+    IdleTimeout(Duration),
+    #[error("session exceeded the time limit of {0:?}")]
+    TotalTimeout(Duration),
+    #[error("the maximum of {0} concurrent sessions is reached")]
+    Busy(usize),
+    #[error("the server is shutting down")]
+    ShuttingDown,
+}
+
+impl SessionError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            SessionError::IdleTimeout(_) | SessionError::TotalTimeout(_) => ErrorKind::Timeout,
+            SessionError::Busy(_) => ErrorKind::Busy,
+            SessionError::ShuttingDown => ErrorKind::ShuttingDown,
+        }
+    }
+}
+
+/// What to tell the client about a failed session.
+fn reply(error: &anyhow::Error) -> Option<RemoteError> {
+    if let Some(error) = error.downcast_ref::<SessionError>() {
+        return Some(RemoteError::new(error.kind(), error.to_string()));
+    }
+    match error.downcast_ref::<HandshakeError>() {
+        // the client was already told why in the handshake
+        Some(HandshakeError::Refused(_)) => None,
+        // only a timeout is worth reporting, other transport errors mean the connection is gone
+        Some(HandshakeError::Transport(inner)) => inner
+            .downcast_ref::<SessionError>()
+            .map(|error| RemoteError::new(error.kind(), error.to_string())),
+        // the client aborted itself
+        None if error.downcast_ref::<RemoteError>().is_some() => None,
+        _ => Some(RemoteError::new(ErrorKind::Internal, error.to_string())),
+    }
+}
+
+/// Runs the sessions of an [`EvaluatorServer`] within [`SessionLimits`].
+pub struct SessionManager {
+    evaluator: Arc<EvaluatorServer>,
+    limits: SessionLimits,
+    permits: Arc<Semaphore>,
+    sessions: TaskTracker,
+    shutdown: CancellationToken,
+}
+
+impl SessionManager {
+    pub fn new(evaluator: Arc<EvaluatorServer>, limits: SessionLimits) -> Self {
+        SessionManager {
+            permits: Arc::new(Semaphore::new(limits.max_sessions)),
+            evaluator,
+            limits,
+            sessions: TaskTracker::new(),
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    pub fn limits(&self) -> &SessionLimits {
+        &self.limits
+    }
+
+    /// Number of sessions currently running.
+    pub fn active(&self) -> usize {
+        self.limits.max_sessions - self.permits.available_permits()
+    }
+
+    /// Runs a session on `transport` in a new task, which [`SessionManager::drain`] waits for.
+    pub fn spawn<T>(
+        &self,
+        mut transport: T,
+        peer: Option<PeerIdentity>,
+    ) -> JoinHandle<Result<Session>>
+    where
+        T: Transport + 'static,
+    {
+        let manager = self.handle();
+        self.sessions
+            .spawn(async move { manager.run(&mut transport, peer.as_ref()).await })
+    }
+
+    /// Runs a session on `transport` within the limits, for a client authenticated as `peer`.
+    /// If the session fails, the client is told why before the error is returned.
+    pub async fn run<T>(&self, transport: &mut T, peer: Option<&PeerIdentity>) -> Result<Session>
+    where
+        T: Transport + ?Sized,
+    {
+        self.handle().run(transport, peer).await
+    }
+
+    /// Stops accepting sessions. New clients are told that the server is shutting down, while
+    /// the sessions in flight continue.
+    pub fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// Resolves once [`SessionManager::shutdown`] was called, to stop accepting connections.
+    pub async fn shutting_down(&self) {
+        self.shutdown.cancelled().await
+    }
+
+    /// Shuts down and waits until every session spawned on this manager has ended.
+    pub async fn drain(&self) {
+        self.shutdown();
+        self.sessions.close();
+        self.sessions.wait().await;
+    }
+
+    fn handle(&self) -> Handle {
+        Handle {
+            evaluator: self.evaluator.clone(),
+            limits: self.limits.clone(),
+            permits: self.permits.clone(),
+            shutdown: self.shutdown.clone(),
+        }
+    }
+}
+
+/// The parts of the manager a session task needs.
+struct Handle {
+    evaluator: Arc<EvaluatorServer>,
+    limits: SessionLimits,
+    permits: Arc<Semaphore>,
+    shutdown: CancellationToken,
+}
+
+impl Handle {
+    async fn run<T>(&self, transport: &mut T, peer: Option<&PeerIdentity>) -> Result<Session>
+    where
+        T: Transport + ?Sized,
+    {
+        let id = SessionId::random();
+        let span = info_span!("session", %id, peer = ?peer.map(PeerIdentity::as_str));
+
+        async move {
+            let result = self.run_limited(transport, peer).await;
+            match &result {
+                Ok(session) => info!(function = %session.function, "session complete"),
+                Err(error) => {
+                    warn!("session failed: {:#}", error);
+                    if let Some(mut reply) = reply(error) {
+                        reply.message = format!("{} (session {})", reply.message, id);
+                        let _ = timeout(self.limits.idle_timeout, transport.abort(reply)).await;
+                    }
+                }
+            }
+            result
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn run_limited<T>(
+        &self,
+        transport: &mut T,
+        peer: Option<&PeerIdentity>,
+    ) -> Result<Session>
+    where
+        T: Transport + ?Sized,
+    {
+        if self.shutdown.is_cancelled() {
+            return Err(SessionError::ShuttingDown.into());
+        }
+        let _permit = self
+            .permits
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| SessionError::Busy(self.limits.max_sessions))?;
+
+        let mut transport = IdleTimeout {
+            inner: transport,
+            timeout: self.limits.idle_timeout,
+        };
+        let session = self.evaluator.serve_peer(&mut transport, peer);
+        match timeout(self.limits.total_timeout, session).await {
+            Ok(result) => result,
+            Err(_) => Err(SessionError::TotalTimeout(self.limits.total_timeout).into()),
+        }
+    }
+}
+
+/// Fails `receive` if the client doesn't send anything for `timeout`.
+struct IdleTimeout<'a, T: ?Sized> {
+    inner: &'a mut T,
+    timeout: Duration,
+}
+
+#[async_trait]
+impl<T: Transport + ?Sized> Transport for IdleTimeout<'_, T> {
+    async fn send(&mut self, message: Vec<u8>) -> Result<()> {
+        match timeout(self.timeout, self.inner.send(message)).await {
+            Ok(result) => result,
+            Err(_) => Err(SessionError::IdleTimeout(self.timeout).into()),
+        }
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        match timeout(self.timeout, self.inner.receive()).await {
+            Ok(result) => result,
+            Err(_) => Err(SessionError::IdleTimeout(self.timeout).into()),
+        }
+    }
+
+    async fn abort(&mut self, error: RemoteError) -> Result<()> {
+        self.inner.abort(error).await
+    }
+}
@@ -0,0 +1,264 @@
+//! Certificates and mutual TLS for QUIC connections between garblers and the evaluator.
+//!
+//! A [`CertificateAuthority`] issues the server certificate and one client certificate per
+//! garbler, with the garbler's identity as the common name. A server configured with
+//! [`server_config`] and the CA certificate only accepts clients presenting a certificate issued
+//! by it, and [`peer_identity`] recovers the identity of the connected garbler for access
+//! control in the [`CircuitRegistry`](crate::registry::CircuitRegistry).
+//!
+//! s2n-quic doesn't expose the certificates of the peer, so mutual TLS runs over quinn.
+
+use crate::registry::PeerIdentity;
+use anyhow::{anyhow, ensure, Context, Result};
+use quinn::crypto::rustls::{QuicClientConfig, QuicServerConfig};
+use quinn::rustls::pki_types::pem::PemObject;
+use quinn::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use quinn::rustls::server::WebPkiClientVerifier;
+use quinn::rustls::{self, crypto, RootCertStore};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair, KeyUsagePurpose,
+};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A PEM encoded certificate and its private key.
+#[derive(Clone, Debug)]
+pub struct IssuedCertificate {
+    pub cert_pem: String,
+    pub key_pem: String,
+}
+
+impl IssuedCertificate {
+    fn new(cert: &Certificate, key: &KeyPair) -> Self {
+        IssuedCertificate {
+            cert_pem: cert.pem(),
+            key_pem: key.serialize_pem(),
+        }
+    }
+
+    /// Writes the certificate to `cert_path` and the key to `key_path`.
+    pub fn write(&self, cert_path: impl AsRef<Path>, key_path: impl AsRef<Path>) -> Result<()> {
+        let (cert_path, key_path) = (cert_path.as_ref(), key_path.as_ref());
+        std::fs::write(cert_path, &self.cert_pem)
+            .with_context(|| format!("writing {}", cert_path.display()))?;
+        write_private(key_path, self.key_pem.as_bytes())
+            .with_context(|| format!("writing {}", key_path.display()))
+    }
+}
+
+/// Writes a private key so that only its owner can read it.
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // `mode` only applies to new files, so also tighten a key file that already existed
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents)
+}
+
+/// Issues the certificates of the server and its clients.
+pub struct CertificateAuthority {
+    cert: Certificate,
+    key: KeyPair,
+}
+
+impl CertificateAuthority {
+    /// Creates a self-signed CA named `name`.
+    pub fn generate(name: &str) -> Result<Self> {
+        let mut params = CertificateParams::new(Vec::<String>::new())?;
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.key_usages = vec![
+            KeyUsagePurpose::KeyCertSign,
+            KeyUsagePurpose::CrlSign,
+            KeyUsagePurpose::DigitalSignature,
+        ];
+
+        let key = KeyPair::generate()?;
+        let cert = params.self_signed(&key)?;
+        Ok(CertificateAuthority { cert, key })
+    }
+
+    /// Loads a CA created before from its PEM encoded certificate and key, to issue more
+    /// certificates.
+    pub fn from_pem(cert_pem: &str, key_pem: &str) -> Result<Self> {
+        let key = KeyPair::from_pem(key_pem)?;
+        let params = CertificateParams::from_ca_cert_pem(cert_pem)?;
+        let cert = params.self_signed(&key)?;
+        Ok(CertificateAuthority { cert, key })
+    }
+
+    /// The CA certificate and key, to be stored with [`IssuedCertificate::write`].
+    pub fn certificate(&self) -> IssuedCertificate {
+        IssuedCertificate::new(&self.cert, &self.key)
+    }
+
+    /// Issues a server certificate valid for the DNS names or IP addresses in `names`.
+    pub fn issue_server(&self, names: &[String]) -> Result<IssuedCertificate> {
+        let mut params = CertificateParams::new(names.to_vec())?;
+        if let Some(name) = names.first() {
+            params.distinguished_name.push(DnType::CommonName, name);
+        }
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        self.issue(params)
+    }
+
+    /// Issues a client certificate for the garbler `identity`.
+    pub fn issue_client(&self, identity: &str) -> Result<IssuedCertificate> {
+        let mut params = CertificateParams::new(Vec::<String>::new())?;
+        params.distinguished_name.push(DnType::CommonName, identity);
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        self.issue(params)
+    }
+
+    fn issue(&self, params: CertificateParams) -> Result<IssuedCertificate> {
+        let key = KeyPair::generate()?;
+        let cert = params.signed_by(&key, &self.cert, &self.key)?;
+        Ok(IssuedCertificate::new(&cert, &key))
+    }
+}
+
+fn provider() -> Arc<crypto::CryptoProvider> {
+    Arc::new(crypto::ring::default_provider())
+}
+
+fn read_certs(pem: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_slice_iter(pem.as_bytes()).collect::<Result<Vec<_>, _>>()?;
+    ensure!(!certs.is_empty(), "no certificate found");
+    Ok(certs)
+}
+
+fn roots(ca_pem: &str) -> Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in read_certs(ca_pem)? {
+        roots.add(cert)?;
+    }
+    Ok(roots)
+}
+
+/// QUIC server configuration with the server's certificate and key. With `client_ca`, clients
+/// must present a certificate issued by that CA.
+pub fn server_config(
+    server: &IssuedCertificate,
+    client_ca: Option<&str>,
+) -> Result<quinn::ServerConfig> {
+    let builder = rustls::ServerConfig::builder_with_provider(provider())
+        .with_protocol_versions(&[&rustls::version::TLS13])?;
+    let builder = match client_ca {
+        Some(ca_pem) => {
+            let verifier =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots(ca_pem)?), provider())
+                    .build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let key = PrivateKeyDer::from_pem_slice(server.key_pem.as_bytes())?;
+    let config = builder.with_single_cert(read_certs(&server.cert_pem)?, key)?;
+    Ok(quinn::ServerConfig::with_crypto(Arc::new(
+        QuicServerConfig::try_from(config)?,
+    )))
+}
+
+/// QUIC client configuration trusting servers with a certificate issued by `ca_pem`, presenting
+/// `identity` if the server requires client authentication.
+pub fn client_config(
+    ca_pem: &str,
+    identity: Option<&IssuedCertificate>,
+) -> Result<quinn::ClientConfig> {
+    let builder = rustls::ClientConfig::builder_with_provider(provider())
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .with_root_certificates(roots(ca_pem)?);
+    let config = match identity {
+        Some(identity) => {
+            let key = PrivateKeyDer::from_pem_slice(identity.key_pem.as_bytes())?;
+            builder.with_client_auth_cert(read_certs(&identity.cert_pem)?, key)?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    Ok(quinn::ClientConfig::new(Arc::new(
+        QuicClientConfig::try_from(config)?,
+    )))
+}
+
+/// Reads a certificate and key from PEM files.
+pub fn load(cert_path: impl AsRef<Path>, key_path: impl AsRef<Path>) -> Result<IssuedCertificate> {
+    let (cert_path, key_path) = (cert_path.as_ref(), key_path.as_ref());
+    Ok(IssuedCertificate {
+        cert_pem: std::fs::read_to_string(cert_path)
+            .with_context(|| format!("reading {}", cert_path.display()))?,
+        key_pem: std::fs::read_to_string(key_path)
+            .with_context(|| format!("reading {}", key_path.display()))?,
+    })
+}
+
+/// The identity of the client of `connection`: the common name of the certificate it
+/// authenticated with, or `None` if it didn't present one.
+pub fn peer_identity(connection: &quinn::Connection) -> Option<PeerIdentity> {
+    let certs = connection
+        .peer_identity()?
+        .downcast::<Vec<CertificateDer<'static>>>()
+        .ok()?;
+    identity_of(certs.first()?).ok()
+}
+
+/// The common name of a DER encoded certificate.
+pub fn identity_of(cert: &CertificateDer<'_>) -> Result<PeerIdentity> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert)?;
+    let name = cert
+        .subject()
+        .iter_common_name()
+        .next()
+        .ok_or_else(|| anyhow!("certificate has no common name"))?
+        .as_str()?;
+    Ok(PeerIdentity::new(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_certificate_identity() -> Result<()> {
+        let ca = CertificateAuthority::generate("test CA")?;
+        let client = ca.issue_client("garbler-1")?;
+        let cert = read_certs(&client.cert_pem)?;
+        assert_eq!(identity_of(&cert[0])?, PeerIdentity::new("garbler-1"));
+
+        // a reloaded CA keeps issuing certificates trusted by the original CA certificate
+        let stored = ca.certificate();
+        let reloaded = CertificateAuthority::from_pem(&stored.cert_pem, &stored.key_pem)?;
+        let server = reloaded.issue_server(&["localhost".to_string()])?;
+        server_config(&server, Some(&stored.cert_pem))?;
+        client_config(&stored.cert_pem, Some(&client))?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_key_permissions() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("circuit-sdk-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let (cert_path, key_path) = (dir.join("ca.pem"), dir.join("ca-key.pem"));
+        std::fs::write(&key_path, "stale")?;
+        std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o644))?;
+
+        CertificateAuthority::generate("test CA")?
+            .certificate()
+            .write(&cert_path, &key_path)?;
+        let mode = std::fs::metadata(&key_path)?.permissions().mode();
+        std::fs::remove_dir_all(&dir)?;
+        assert_eq!(mode & 0o777, 0o600);
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+//! Conversion between typed values and the input and output bits of a circuit.
+//!
+//! Types are named as in the function signature recorded in a `CircuitArtifact`: `bool` and the
+//! primitive integer types up to 128 bits. Bits are ordered least-significant bit first.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use compute::operations::artifact::InputParameter;
+use compute::prelude::{CircuitArtifact, Party};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// An input value as written in a config file or on the command line, before it is
+/// interpreted according to the type of its parameter.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum InputValue {
+    Bool(bool),
+    Integer(i64),
+    Text(String),
+}
+
+impl From<&str> for InputValue {
+    fn from(text: &str) -> Self {
+        InputValue::Text(text.to_string())
+    }
+}
+
+/// A decoded value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value {
+    Bool(bool),
+    Unsigned(u128),
+    Signed(i128),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Unsigned(value) => write!(f, "{value}"),
+            Value::Signed(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Unsigned,
+    Signed,
+}
+
+fn kind(ty: &str) -> Result<Kind> {
+    match ty.trim() {
+        "bool" => Ok(Kind::Bool),
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => Ok(Kind::Unsigned),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => Ok(Kind::Signed),
+        other => bail!("unsupported type `{other}`"),
+    }
+}
+
+impl Value {
+    /// Interprets `input` as a value of type `ty`.
+    pub fn parse(ty: &str, input: &InputValue) -> Result<Value> {
+        let value = match (kind(ty)?, input) {
+            (Kind::Bool, InputValue::Bool(value)) => Value::Bool(*value),
+            (Kind::Bool, InputValue::Text(text)) => Value::Bool(text.trim().parse()?),
+            (Kind::Unsigned, InputValue::Integer(value)) => {
+                Value::Unsigned(u128::try_from(*value).map_err(|_| anyhow!("{value} is negative"))?)
+            }
+            (Kind::Unsigned, InputValue::Text(text)) => Value::Unsigned(text.trim().parse()?),
+            (Kind::Signed, InputValue::Integer(value)) => Value::Signed(*value as i128),
+            (Kind::Signed, InputValue::Text(text)) => Value::Signed(text.trim().parse()?),
+            (_, input) => bail!("{input:?} is not a valid `{ty}`"),
+        };
+        Ok(value)
+    }
+
+    /// Encodes the value in `width` bits, failing if it doesn't fit.
+    pub fn to_bits(self, width: usize) -> Result<Vec<bool>> {
+        ensure!(width <= 128, "values wider than 128 bits are not supported");
+        let raw = match self {
+            Value::Bool(value) => value as u128,
+            Value::Unsigned(value) => {
+                ensure!(
+                    width == 128 || value >> width == 0,
+                    "{value} doesn't fit in {width} bits"
+                );
+                value
+            }
+            Value::Signed(value) => {
+                let fits = width == 128
+                    || (width > 0 && (value >> (width - 1) == 0 || value >> (width - 1) == -1));
+                ensure!(fits, "{value} doesn't fit in {width} bits");
+                value as u128
+            }
+        };
+        Ok((0..width).map(|i| (raw >> i) & 1 == 1).collect())
+    }
+
+    /// Decodes `bits` as a value of type `ty`. Signed values are sign-extended from the
+    /// width of `bits`.
+    pub fn from_bits(ty: &str, bits: &[bool]) -> Result<Value> {
+        ensure!(
+            bits.len() <= 128,
+            "values wider than 128 bits are not supported"
+        );
+        let raw = bits
+            .iter()
+            .enumerate()
+            .fold(0u128, |raw, (i, &bit)| raw | (bit as u128) << i);
+
+        let value = match kind(ty)? {
+            Kind::Bool => Value::Bool(raw != 0),
+            Kind::Unsigned => Value::Unsigned(raw),
+            Kind::Signed => {
+                let shift = 128 - bits.len().max(1);
+                Value::Signed((raw as i128) << shift >> shift)
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// Encodes `input` as the bits of `parameter`.
+pub fn encode_parameter(parameter: &InputParameter, input: &InputValue) -> Result<Vec<bool>> {
+    Value::parse(&parameter.ty, input)
+        .and_then(|value| value.to_bits(parameter.width))
+        .with_context(|| format!("invalid value for parameter `{}`", parameter.name))
+}
+
+/// Encodes the garbler inputs of `artifact` from the value of each parameter, by name.
+pub fn bind_inputs(
+    artifact: &CircuitArtifact,
+    inputs: &BTreeMap<String, InputValue>,
+) -> Result<Vec<bool>> {
+    bind_party_inputs(artifact, Party::Garbler, inputs)
+}
+
+/// Encodes the inputs of `party` to `artifact` from the value of each parameter, by name.
+/// Values of the other party's parameters are ignored.
+pub fn bind_party_inputs(
+    artifact: &CircuitArtifact,
+    party: Party,
+    inputs: &BTreeMap<String, InputValue>,
+) -> Result<Vec<bool>> {
+    let layout = artifact.layout();
+    if let Some(unknown) = inputs.keys().find(|name| layout.parameter(name).is_none()) {
+        bail!(
+            "`{}` has no parameter `{}`",
+            artifact.signature().name,
+            unknown
+        );
+    }
+
+    let arguments = layout
+        .parameters_of(party)
+        .map(|parameter| {
+            let input = inputs
+                .get(&parameter.name)
+                .with_context(|| format!("missing value for parameter `{}`", parameter.name))?;
+            encode_parameter(parameter, input)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let arguments: Vec<&[bool]> = arguments.iter().map(Vec::as_slice).collect();
+    Ok(layout.bind(party, &arguments)?)
+}
+
+/// Decodes the output of `artifact` according to the return type of its function.
+pub fn decode_output(artifact: &CircuitArtifact, bits: &[bool]) -> Result<Value> {
+    Value::from_bits(&artifact.signature().output, bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let bits = Value::parse("u8", &InputValue::Integer(200))?.to_bits(8)?;
+        assert_eq!(Value::from_bits("u8", &bits)?, Value::Unsigned(200));
+
+        let bits = Value::parse("i16", &"-3".into())?.to_bits(16)?;
+        assert_eq!(Value::from_bits("i16", &bits)?, Value::Signed(-3));
+
+        let bits = Value::parse("bool", &InputValue::Bool(true))?.to_bits(1)?;
+        assert_eq!(bits, vec![true]);
+        assert_eq!(Value::from_bits("bool", &bits)?.to_string(), "true");
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_values_out_of_range() {
+        assert!(Value::Unsigned(256).to_bits(8).is_err());
+        assert!(Value::Signed(-129).to_bits(8).is_err());
+        assert!(Value::Signed(-128).to_bits(8).is_ok());
+        assert!(Value::parse("u8", &InputValue::Integer(-1)).is_err());
+        assert!(Value::parse("u8", &InputValue::Bool(true)).is_err());
+        assert!(Value::parse("f32", &"1.0".into()).is_err());
+    }
+}
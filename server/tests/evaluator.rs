@@ -0,0 +1,196 @@
+use compute::operations::artifact::InputParameter;
+use compute::prelude::*;
+use compute::protocol::{self, run_garbler};
+use compute::report::Direction;
+use compute::transcript::Transcript;
+use compute::transport::ChannelTransport;
+use server::evaluator::{EvaluatorServer, NoInputs};
+use server::handshake::announce;
+use server::value::{bind_inputs, decode_output, InputValue, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[encrypted(artifact)]
+fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
+    let res = a * b;
+    let res = res + c;
+    res - d
+}
+
+#[encrypted(artifact)]
+fn addition(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+/// `a & b`, where `a` comes from the garbler and `b` from the evaluator.
+fn and_gate() -> Arc<CircuitArtifact> {
+    let circuit = Circuit::new(
+        vec![Gate::InContrib, Gate::InEval, Gate::And(0, 1)],
+        vec![2],
+    );
+    let parameter = |name: &str, party| InputParameter {
+        name: name.to_string(),
+        ty: "bool".to_string(),
+        party,
+        offset: 0,
+        width: 1,
+    };
+    let layout = InputLayout::new(
+        vec![
+            parameter("a", Party::Garbler),
+            parameter("b", Party::Evaluator),
+        ],
+        vec![],
+    );
+    let signature = FunctionSignature::new("and_gate", "bool");
+    Arc::new(CircuitArtifact::new(signature, layout, circuit).unwrap())
+}
+
+async fn call(
+    server: Arc<EvaluatorServer>,
+    function: &str,
+    artifact: &CircuitArtifact,
+    arguments: &[&[bool]],
+) -> anyhow::Result<Vec<bool>> {
+    let (mut client, mut transport) = ChannelTransport::pair();
+    let session = tokio::spawn(async move { server.serve(&mut transport).await });
+
+    announce(&mut client, function, artifact.blake3_hash()).await?;
+    let input = artifact.layout().bind(Party::Garbler, arguments)?;
+    let output = run_garbler(&mut client, artifact.circuit(), &input).await?;
+
+    let session = session.await??;
+    assert_eq!(session.function, function);
+    assert_eq!(session.output, output);
+    Ok(output)
+}
+
+#[tokio::test]
+async fn test_serves_multiple_functions() -> anyhow::Result<()> {
+    let mut server = EvaluatorServer::new();
+    server.register("multi_arithmetic", multi_arithmetic::<u8>());
+    server.register("addition", addition::<u8>());
+    let server = Arc::new(server);
+
+    let inputs: Vec<GarbledUint<8>> = [2_u8, 5, 3, 4].iter().map(|&x| x.into()).collect();
+    let arguments: Vec<&[bool]> = inputs.iter().map(|x| x.bits.as_slice()).collect();
+
+    let output = call(
+        server.clone(),
+        "multi_arithmetic",
+        &multi_arithmetic::<u8>(),
+        &arguments,
+    )
+    .await?;
+    let result: u8 = GarbledUint::<8>::new(output).into();
+    assert_eq!(result, 9);
+
+    let output = call(server, "addition", &addition::<u8>(), &arguments[..2]).await?;
+    let result: u8 = GarbledUint::<8>::new(output).into();
+    assert_eq!(result, 7);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_evaluator_inputs_from_provider() -> anyhow::Result<()> {
+    let mut server = EvaluatorServer::new();
+    server.register_with_inputs("and_gate", and_gate(), |_: &CircuitArtifact| Ok(vec![true]));
+    let server = Arc::new(server);
+
+    let output = call(server.clone(), "and_gate", &and_gate(), &[&[true]]).await?;
+    assert_eq!(output, vec![true]);
+    let output = call(server, "and_gate", &and_gate(), &[&[false]]).await?;
+    assert_eq!(output, vec![false]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_artifact_file() -> anyhow::Result<()> {
+    let path = std::env::temp_dir().join(format!("server-addition-{}.bin", std::process::id()));
+    addition::<u8>().save(&path)?;
+
+    let mut server = EvaluatorServer::new();
+    let registered = server.register_file("addition", &path, NoInputs);
+    std::fs::remove_file(&path)?;
+    registered?;
+
+    let artifact = server.registry().get("addition").unwrap();
+    assert_eq!(artifact.blake3_hash(), addition::<u8>().blake3_hash());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_records_transcripts() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("server-transcripts-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let mut server = EvaluatorServer::new();
+    server.register("addition", addition::<u8>());
+    server.record_transcripts(&dir);
+
+    let output = call(
+        Arc::new(server),
+        "addition",
+        &addition::<u8>(),
+        &[&[false; 8], &[true; 8]],
+    )
+    .await;
+    let files: Vec<_> = std::fs::read_dir(&dir)?.collect::<Result<_, _>>()?;
+    let transcript = files.first().map(|file| Transcript::load(file.path()));
+    std::fs::remove_dir_all(&dir)?;
+    assert_eq!(files.len(), 1);
+    let output = output?;
+
+    // the evaluator records the protocol messages only, ending with the output it sent
+    let transcript = transcript.unwrap()?;
+    let first = &transcript.messages[0];
+    assert_eq!(
+        (first.step, first.direction),
+        (0, Direction::GarblerToEvaluator)
+    );
+    let last = transcript.messages.last().unwrap();
+    assert_eq!(last.direction, Direction::EvaluatorToGarbler);
+    assert_eq!(protocol::decode_output(&last.data)?, output);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_wrong_number_of_evaluator_inputs() {
+    let mut server = EvaluatorServer::new();
+    server.register("and_gate", and_gate());
+    let server = Arc::new(server);
+
+    let (mut client, mut transport) = ChannelTransport::pair();
+    let session = tokio::spawn(async move { server.serve(&mut transport).await });
+    announce(&mut client, "and_gate", and_gate().blake3_hash())
+        .await
+        .unwrap();
+
+    let err = session.await.unwrap().unwrap_err();
+    let message = format!("{err:#}");
+    assert!(
+        message.contains("parameter `b: bool` expects 1 bits, got 0"),
+        "{message}"
+    );
+}
+
+#[tokio::test]
+async fn test_typed_inputs_and_output() -> anyhow::Result<()> {
+    let mut server = EvaluatorServer::new();
+    server.register("multi_arithmetic", multi_arithmetic::<u8>());
+    let server = Arc::new(server);
+    let artifact = multi_arithmetic::<u8>();
+
+    let mut inputs: BTreeMap<String, InputValue> = [("a", 2), ("b", 5), ("c", 3)]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), InputValue::Integer(value)))
+        .collect();
+    let missing = bind_inputs(&artifact, &inputs).unwrap_err();
+    assert!(missing.to_string().contains("`d`"), "{missing}");
+
+    inputs.insert("d".to_string(), "4".into());
+    let input = bind_inputs(&artifact, &inputs)?;
+    let arguments: Vec<&[bool]> = input.chunks(8).take(4).collect();
+    let output = call(server, "multi_arithmetic", &artifact, &arguments).await?;
+    assert_eq!(decode_output(&artifact, &output)?, Value::Unsigned(9));
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use compute::prelude::*;
+use compute::protocol::{run_evaluator, run_garbler};
+use compute::transport::ChannelTransport;
+use server::handshake::{accept, announce, HandshakeError, Rejection};
+use server::registry::{CircuitRegistry, PeerIdentity};
+
+#[encrypted(artifact)]
+fn multi_arithmetic(a: u8, b: u8, c: u8, d: u8) -> u8 {
+    let res = a * b;
+    let res = res + c;
+    res - d
+}
+
+#[encrypted(artifact)]
+fn addition(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+fn registry() -> CircuitRegistry {
+    let mut registry = CircuitRegistry::new();
+    registry.register("multi_arithmetic", multi_arithmetic::<u8>());
+    registry
+}
+
+#[tokio::test]
+async fn test_handshake_then_protocol() -> anyhow::Result<()> {
+    let (mut client, mut server) = ChannelTransport::pair();
+    let artifact = multi_arithmetic::<u8>();
+
+    let server = tokio::spawn(async move {
+        let (name, artifact) = accept(&mut server, &registry(), None).await?;
+        assert_eq!(name, "multi_arithmetic");
+        run_evaluator(&mut server, artifact.circuit(), &[]).await
+    });
+
+    announce(&mut client, "multi_arithmetic", artifact.blake3_hash()).await?;
+    let inputs: Vec<GarbledUint<8>> = [2_u8, 5, 3, 4].iter().map(|&x| x.into()).collect();
+    let arguments: Vec<&[bool]> = inputs.iter().map(|x| x.bits.as_slice()).collect();
+    let input = artifact.layout().bind(Party::Garbler, &arguments)?;
+    let output = run_garbler(&mut client, artifact.circuit(), &input).await?;
+
+    assert_eq!(server.await??, output);
+    let result: u8 = GarbledUint::<8>::new(output).into();
+    assert_eq!(result, 9);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_handshake_by_hash() -> anyhow::Result<()> {
+    let (mut client, mut server) = ChannelTransport::pair();
+    let server = tokio::spawn(async move { accept(&mut server, &registry(), None).await });
+
+    announce(&mut client, "", multi_arithmetic::<u8>().blake3_hash()).await?;
+    let (name, _) = server.await??;
+    assert_eq!(name, "multi_arithmetic");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_handshake_rejects_unknown_function() {
+    let (mut client, mut server) = ChannelTransport::pair();
+    let server = tokio::spawn(async move { accept(&mut server, &registry(), None).await });
+
+    let result = announce(&mut client, "addition", addition::<u8>().blake3_hash()).await;
+    assert!(matches!(
+        result,
+        Err(HandshakeError::Rejected(Rejection::UnknownFunction(ref name))) if name == "addition"
+    ));
+    assert!(matches!(
+        server.await.unwrap(),
+        Err(HandshakeError::Refused(Rejection::UnknownFunction(_)))
+    ));
+}
+
+#[tokio::test]
+async fn test_handshake_rejects_hash_mismatch() {
+    let (mut client, mut server) = ChannelTransport::pair();
+    let server = tokio::spawn(async move { accept(&mut server, &registry(), None).await });
+
+    // same name, different circuit
+    let result = announce(
+        &mut client,
+        "multi_arithmetic",
+        addition::<u8>().blake3_hash(),
+    )
+    .await;
+    let Err(HandshakeError::Rejected(Rejection::HashMismatch { expected, .. })) = result else {
+        panic!("expected a hash mismatch, got {:?}", result);
+    };
+    assert_eq!(expected, multi_arithmetic::<u8>().blake3_hash());
+    assert!(server.await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn test_handshake_enforces_access_control() -> anyhow::Result<()> {
+    let mut registry = registry();
+    registry.restrict("multi_arithmetic", [PeerIdentity::new("garbler-1")]);
+    let hash = multi_arithmetic::<u8>().blake3_hash();
+
+    for (peer, allowed) in [
+        (Some("garbler-1"), true),
+        (Some("garbler-2"), false),
+        (None, false),
+    ] {
+        let (mut client, mut server) = ChannelTransport::pair();
+        let registry = registry.clone();
+        let server = tokio::spawn(async move {
+            let peer = peer.map(PeerIdentity::new);
+            accept(&mut server, &registry, peer.as_ref()).await
+        });
+
+        let result = announce(&mut client, "multi_arithmetic", hash).await;
+        if allowed {
+            result?;
+            server.await??;
+        } else {
+            // the client can't tell a forbidden function from a missing one
+            assert!(matches!(
+                result,
+                Err(HandshakeError::Rejected(Rejection::UnknownFunction(_)))
+            ));
+            assert!(matches!(
+                server.await?,
+                Err(HandshakeError::Refused(Rejection::Forbidden { .. }))
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_find_by_hash_is_deterministic() {
+    let artifact = addition::<u8>();
+    let hash = artifact.blake3_hash();
+    let mut registry = CircuitRegistry::new();
+    for name in ["sum-c", "sum-a", "sum-b"] {
+        registry.register(name, artifact.clone());
+    }
+    let garbler = PeerIdentity::new("garbler-1");
+    registry.restrict("sum-a", [garbler.clone()]);
+
+    assert_eq!(registry.find_by_hash(&hash).unwrap().0, "sum-a");
+    // a client is given a name it may run if there is one
+    let found = registry.find_by_hash_for(&hash, Some(&garbler)).unwrap();
+    assert_eq!(found.0, "sum-a");
+    assert_eq!(registry.find_by_hash_for(&hash, None).unwrap().0, "sum-b");
+
+    // replacing a function moves it to the hash of its new artifact
+    registry.register("sum-a", multi_arithmetic::<u8>());
+    assert_eq!(registry.find_by_hash(&hash).unwrap().0, "sum-b");
+}
@@ -0,0 +1,196 @@
+#![cfg(feature = "resume")]
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use compute::prelude::*;
+use compute::transport::{ChannelTransport, Transport};
+use server::evaluator::EvaluatorServer;
+use server::handshake::{self, HandshakeError, Rejection};
+use server::registry::PeerIdentity;
+use server::resume::run_garbler;
+use server::session::{SessionLimits, SessionManager};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[encrypted(artifact)]
+fn addition(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+/// Loses the connection when sending more than `sends` messages, without the other end noticing.
+struct Flaky {
+    inner: Option<ChannelTransport>,
+    lost: Option<ChannelTransport>,
+    sends: usize,
+}
+
+#[async_trait]
+impl Transport for Flaky {
+    async fn send(&mut self, message: Vec<u8>) -> Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) if self.sends > 0 => {
+                self.sends -= 1;
+                inner.send(message).await
+            }
+            _ => {
+                self.lost = self.inner.take().or(self.lost.take());
+                bail!("connection lost")
+            }
+        }
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.receive().await,
+            None => bail!("connection lost"),
+        }
+    }
+}
+
+fn manager() -> Arc<SessionManager> {
+    let mut evaluator = EvaluatorServer::new();
+    evaluator.register("addition", addition::<u8>());
+    Arc::new(SessionManager::new(
+        Arc::new(evaluator),
+        SessionLimits::default(),
+    ))
+}
+
+fn input() -> Result<Vec<bool>> {
+    let inputs: Vec<GarbledUint<8>> = vec![20_u8.into(), 22_u8.into()];
+    let arguments: Vec<&[bool]> = inputs.iter().map(|x| x.bits.as_slice()).collect();
+    Ok(addition::<u8>().layout().bind(Party::Garbler, &arguments)?)
+}
+
+#[tokio::test]
+async fn test_resumes_session_after_connection_loss() -> Result<()> {
+    let manager = manager();
+    let mut connections = 0;
+    let connect = || {
+        connections += 1;
+        // the first connection drops after the garbler's first protocol message, which the
+        // server doesn't notice before the client reconnects
+        let sends = if connections == 1 { 3 } else { usize::MAX };
+        let (client, server) = ChannelTransport::pair();
+        manager.spawn(server, None);
+        async move {
+            Ok(Flaky {
+                inner: Some(client),
+                lost: None,
+                sends,
+            })
+        }
+    };
+
+    let output = run_garbler(connect, "addition", &addition::<u8>(), &input()?, 3).await?;
+    assert_eq!(u8::from(GarbledUint::<8>::new(output)), 42);
+    assert_eq!(connections, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_the_opening_client_can_resume() -> Result<()> {
+    let mut evaluator = EvaluatorServer::new();
+    evaluator.register("addition", addition::<u8>());
+    let evaluator = Arc::new(evaluator);
+    let garbler = PeerIdentity::new("garbler-1");
+    let artifact = addition::<u8>();
+
+    // opens a session and drops the connection right away
+    let (mut client, mut transport) = ChannelTransport::pair();
+    let session = tokio::spawn({
+        let (evaluator, garbler) = (evaluator.clone(), garbler.clone());
+        async move { evaluator.serve_peer(&mut transport, Some(&garbler)).await }
+    });
+    let id = handshake::open(&mut client, "addition", artifact.blake3_hash()).await?;
+    drop(client);
+    assert!(session.await?.is_err());
+    assert_eq!(evaluator.suspended().len(), 1);
+
+    for peer in [None, Some(PeerIdentity::new("garbler-2"))] {
+        let (mut client, mut transport) = ChannelTransport::pair();
+        let session = tokio::spawn({
+            let evaluator = evaluator.clone();
+            async move { evaluator.serve_peer(&mut transport, peer.as_ref()).await }
+        });
+        let error = handshake::resume(&mut client, id, artifact.blake3_hash())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            HandshakeError::Rejected(Rejection::UnknownSession(unknown)) if unknown == id
+        ));
+        assert!(session.await?.is_err());
+    }
+    assert_eq!(evaluator.suspended().len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refuses_to_resume_unknown_sessions() -> Result<()> {
+    let manager = manager();
+    let connect = || {
+        let (client, server) = ChannelTransport::pair();
+        manager.spawn(server, None);
+        async move { Ok(client) }
+    };
+    let artifact = addition::<u8>();
+    let output = run_garbler(connect, "addition", &artifact, &input()?, 0).await?;
+    assert_eq!(u8::from(GarbledUint::<8>::new(output)), 42);
+
+    // the completed session is gone, like any session the server doesn't know
+    let (mut client, server) = ChannelTransport::pair();
+    manager.spawn(server, None);
+    let unknown = server::session::SessionId::random();
+    let error = handshake::resume(&mut client, unknown, artifact.blake3_hash())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        HandshakeError::Rejected(Rejection::UnknownSession(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_forgets_sessions_after_the_resume_window() -> Result<()> {
+    let mut evaluator = EvaluatorServer::new();
+    evaluator.register("addition", addition::<u8>());
+    evaluator.set_resume_window(Duration::from_millis(50));
+    let evaluator = Arc::new(evaluator);
+
+    let (mut client, mut transport) = ChannelTransport::pair();
+    let session = tokio::spawn({
+        let evaluator = evaluator.clone();
+        async move { evaluator.serve(&mut transport).await }
+    });
+    handshake::open(&mut client, "addition", addition::<u8>().blake3_hash()).await?;
+    drop(client);
+    assert!(session.await?.is_err());
+    assert_eq!(evaluator.suspended().len(), 1);
+
+    // no other session touches the suspended sessions in the meantime
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(evaluator.suspended().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_forgets_cancelled_sessions() -> Result<()> {
+    let mut evaluator = EvaluatorServer::new();
+    evaluator.register("addition", addition::<u8>());
+    let evaluator = Arc::new(evaluator);
+
+    // the session is cancelled, e.g. by a timeout, while it waits for the garbler
+    let (mut client, mut transport) = ChannelTransport::pair();
+    let session = tokio::spawn({
+        let evaluator = evaluator.clone();
+        async move { evaluator.serve(&mut transport).await }
+    });
+    handshake::open(&mut client, "addition", addition::<u8>().blake3_hash()).await?;
+    assert_eq!(evaluator.suspended().len(), 1);
+    session.abort();
+    assert!(session.await.unwrap_err().is_cancelled());
+    assert!(evaluator.suspended().is_empty());
+    Ok(())
+}
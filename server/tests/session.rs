@@ -0,0 +1,163 @@
+use compute::prelude::*;
+use compute::protocol::run_garbler;
+use compute::transport::{ChannelTransport, ErrorKind, RemoteError, Transport};
+use server::evaluator::EvaluatorServer;
+use server::handshake::announce;
+use server::session::{SessionError, SessionLimits, SessionManager};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[encrypted(artifact)]
+fn addition(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+fn manager(limits: SessionLimits) -> Arc<SessionManager> {
+    let mut evaluator = EvaluatorServer::new();
+    evaluator.register("addition", addition::<u8>());
+    Arc::new(SessionManager::new(Arc::new(evaluator), limits))
+}
+
+fn limits(idle_timeout: Duration, total_timeout: Duration) -> SessionLimits {
+    SessionLimits {
+        idle_timeout,
+        total_timeout,
+        max_sessions: 4,
+    }
+}
+
+async fn run_addition(transport: &mut ChannelTransport) -> anyhow::Result<u8> {
+    let artifact = addition::<u8>();
+    announce(transport, "addition", artifact.blake3_hash()).await?;
+
+    let inputs: Vec<GarbledUint<8>> = vec![20_u8.into(), 22_u8.into()];
+    let arguments: Vec<&[bool]> = inputs.iter().map(|x| x.bits.as_slice()).collect();
+    let input = artifact.layout().bind(Party::Garbler, &arguments)?;
+    let output = run_garbler(transport, artifact.circuit(), &input).await?;
+    Ok(GarbledUint::<8>::new(output).into())
+}
+
+/// The error the server aborted the session of `client` with.
+async fn remote_error(client: &mut ChannelTransport) -> RemoteError {
+    let error = client.receive().await.unwrap_err();
+    error
+        .downcast_ref::<RemoteError>()
+        .cloned()
+        .unwrap_or_else(|| panic!("expected a remote error, got {error:#}"))
+}
+
+async fn wait_until_active(manager: &SessionManager, sessions: usize) {
+    while manager.active() < sessions {
+        tokio::task::yield_now().await;
+    }
+}
+
+#[tokio::test]
+async fn test_session_completes() -> anyhow::Result<()> {
+    let manager = manager(SessionLimits::default());
+    let (mut client, transport) = ChannelTransport::pair();
+    let session = manager.spawn(transport, None);
+
+    assert_eq!(run_addition(&mut client).await?, 42);
+    assert_eq!(session.await??.function, "addition");
+    assert_eq!(manager.active(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_idle_client_times_out() -> anyhow::Result<()> {
+    let manager = manager(limits(Duration::from_millis(50), Duration::from_secs(60)));
+    let (mut client, transport) = ChannelTransport::pair();
+    let session = manager.spawn(transport, None);
+
+    // the client never announces a function
+    let error = remote_error(&mut client).await;
+    assert_eq!(error.kind, ErrorKind::Timeout);
+
+    let error = session.await?.unwrap_err();
+    assert!(format!("{error:#}").contains("no message from the client"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_exceeding_total_timeout_is_aborted() -> anyhow::Result<()> {
+    let manager = manager(limits(Duration::from_secs(60), Duration::from_millis(50)));
+    let (mut client, transport) = ChannelTransport::pair();
+    let session = manager.spawn(transport, None);
+
+    // the client keeps the session open after the handshake without sending its inputs
+    let artifact = addition::<u8>();
+    announce(&mut client, "addition", artifact.blake3_hash()).await?;
+    let error = remote_error(&mut client).await;
+    assert_eq!(error.kind, ErrorKind::Timeout);
+    assert!(matches!(
+        session.await?.unwrap_err().downcast_ref::<SessionError>(),
+        Some(SessionError::TotalTimeout(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_sessions_beyond_the_limit() -> anyhow::Result<()> {
+    let manager = manager(SessionLimits {
+        max_sessions: 1,
+        ..SessionLimits::default()
+    });
+    let (mut first, transport) = ChannelTransport::pair();
+    let running = manager.spawn(transport, None);
+    wait_until_active(&manager, 1).await;
+
+    let (mut second, transport) = ChannelTransport::pair();
+    let rejected = manager.spawn(transport, None);
+    assert_eq!(remote_error(&mut second).await.kind, ErrorKind::Busy);
+    assert!(rejected.await?.is_err());
+
+    // the running session is unaffected and frees its slot when done
+    assert_eq!(run_addition(&mut first).await?, 42);
+    running.await??;
+    let (mut third, transport) = ChannelTransport::pair();
+    let session = manager.spawn(transport, None);
+    assert_eq!(run_addition(&mut third).await?, 42);
+    session.await??;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_rejects_new_sessions() -> anyhow::Result<()> {
+    let manager = manager(SessionLimits::default());
+    manager.shutdown();
+    assert!(manager.is_shutting_down());
+
+    let (mut client, transport) = ChannelTransport::pair();
+    let session = manager.spawn(transport, None);
+    assert_eq!(
+        remote_error(&mut client).await.kind,
+        ErrorKind::ShuttingDown
+    );
+    assert!(matches!(
+        session.await?.unwrap_err().downcast_ref::<SessionError>(),
+        Some(SessionError::ShuttingDown)
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_drain_waits_for_sessions_in_flight() -> anyhow::Result<()> {
+    let manager = manager(SessionLimits::default());
+    let (mut client, transport) = ChannelTransport::pair();
+    let session = manager.spawn(transport, None);
+    wait_until_active(&manager, 1).await;
+
+    let drain = tokio::spawn({
+        let manager = manager.clone();
+        async move { manager.drain().await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!drain.is_finished());
+    assert!(manager.is_shutting_down());
+
+    assert_eq!(run_addition(&mut client).await?, 42);
+    drain.await?;
+    session.await??;
+    Ok(())
+}
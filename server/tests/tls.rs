@@ -0,0 +1,89 @@
+use compute::prelude::*;
+use compute::protocol::run_garbler;
+use compute::transport::QuicTransport;
+use server::evaluator::EvaluatorServer;
+use server::handshake::{announce, HandshakeError, Rejection};
+use server::registry::PeerIdentity;
+use server::session::SessionManager;
+use server::tls::{self, CertificateAuthority, IssuedCertificate};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+#[encrypted(artifact)]
+fn addition(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+/// Starts an evaluator requiring client certificates from `ca`, where only `garbler-1` may run
+/// `addition`.
+fn start_server(ca: &CertificateAuthority) -> anyhow::Result<SocketAddr> {
+    let mut evaluator = EvaluatorServer::new();
+    evaluator.register("addition", addition::<u8>());
+    evaluator.restrict("addition", [PeerIdentity::new("garbler-1")]);
+
+    let server = ca.issue_server(&["localhost".to_string()])?;
+    let config = tls::server_config(&server, Some(&ca.certificate().cert_pem))?;
+    let endpoint = quinn::Endpoint::server(config, "127.0.0.1:0".parse()?)?;
+    let addr = endpoint.local_addr()?;
+    let sessions = SessionManager::new(Arc::new(evaluator), Default::default());
+    tokio::spawn(server::quic::listen(Arc::new(sessions), endpoint));
+    Ok(addr)
+}
+
+async fn connect(
+    ca: &CertificateAuthority,
+    addr: SocketAddr,
+    identity: Option<&IssuedCertificate>,
+) -> anyhow::Result<QuicTransport> {
+    let mut endpoint = quinn::Endpoint::client("127.0.0.1:0".parse()?)?;
+    endpoint.set_default_client_config(tls::client_config(&ca.certificate().cert_pem, identity)?);
+    let connection = endpoint.connect(addr, "localhost")?.await?;
+    QuicTransport::open(&connection).await
+}
+
+async fn run_addition(transport: &mut QuicTransport) -> anyhow::Result<u8> {
+    let artifact = addition::<u8>();
+    announce(transport, "addition", artifact.blake3_hash()).await?;
+
+    let inputs: Vec<GarbledUint<8>> = vec![20_u8.into(), 22_u8.into()];
+    let arguments: Vec<&[bool]> = inputs.iter().map(|x| x.bits.as_slice()).collect();
+    let input = artifact.layout().bind(Party::Garbler, &arguments)?;
+    let output = run_garbler(transport, artifact.circuit(), &input).await?;
+    Ok(GarbledUint::<8>::new(output).into())
+}
+
+#[tokio::test]
+async fn test_mutual_tls_access_control() -> anyhow::Result<()> {
+    let ca = CertificateAuthority::generate("test CA")?;
+    let addr = start_server(&ca)?;
+
+    let allowed = ca.issue_client("garbler-1")?;
+    let mut transport = connect(&ca, addr, Some(&allowed)).await?;
+    assert_eq!(run_addition(&mut transport).await?, 42);
+
+    let other = ca.issue_client("garbler-2")?;
+    let mut transport = connect(&ca, addr, Some(&other)).await?;
+    let error = run_addition(&mut transport).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<HandshakeError>(),
+        Some(HandshakeError::Rejected(Rejection::UnknownFunction(_)))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_clients_without_trusted_certificate() -> anyhow::Result<()> {
+    let ca = CertificateAuthority::generate("test CA")?;
+    let addr = start_server(&ca)?;
+
+    // without a certificate
+    let result = async { run_addition(&mut connect(&ca, addr, None).await?).await }.await;
+    assert!(result.is_err());
+
+    // with a certificate from another CA
+    let untrusted = CertificateAuthority::generate("other CA")?.issue_client("garbler-1")?;
+    let result =
+        async { run_addition(&mut connect(&ca, addr, Some(&untrusted)).await?).await }.await;
+    assert!(result.is_err());
+    Ok(())
+}